@@ -0,0 +1,74 @@
+//! Управление умным домом командами со стандартного ввода.
+//!
+//! Поддерживаемые команды (по одной на строку):
+//!
+//! - `add-room <room>` — добавить комнату;
+//! - `plug <room> <device> [power]` — подключить к комнате розетку
+//!   с заданной мощностью в ваттах;
+//! - `report` — вывести отчет о доме.
+//!
+//! ```plaintext
+//! $ printf 'add-room Kitchen\nplug Kitchen Kettle 2000\nreport\n' | cargo run --example house_cli
+//! ```
+
+use std::io::{self, BufRead};
+use std::sync::Arc;
+
+use lesson_4::smart::device::SmartSocket;
+use lesson_4::smart::location::{SmartHouse, SmartRoom};
+use lesson_4::smart::report::TreeReport;
+
+fn main() {
+    let mut house = SmartHouse::new("CLI house".to_string());
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("read error: {e}");
+                break;
+            }
+        };
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+
+        match args.as_slice() {
+            [] => continue,
+            ["add-room", room] => match house.add(SmartRoom::new(room.to_string())) {
+                Ok(()) => println!("room {room} added"),
+                Err(e) => println!("error: {e}"),
+            },
+            ["plug", room, device, rest @ ..] => {
+                let power = match rest {
+                    [] => 0.0,
+                    [power] => match power.parse::<f64>() {
+                        Ok(power) => power,
+                        Err(_) => {
+                            println!("error: invalid power {power:?}");
+                            continue;
+                        }
+                    },
+                    _ => {
+                        println!("usage: plug <room> <device> [power]");
+                        continue;
+                    }
+                };
+
+                let socket = SmartSocket::with_power(device.to_string(), power);
+
+                match house.get_room_mut(room) {
+                    Some(mut r) => match r.plug(Arc::new(socket)) {
+                        Ok(()) => println!("device {device} plugged into {room}"),
+                        Err(e) => println!("error: {e}"),
+                    },
+                    None => println!("error: room {room} not found"),
+                }
+            }
+            ["report"] => match house.create_report(TreeReport) {
+                Ok(report) => print!("{report}"),
+                Err(e) => println!("error: {e}"),
+            },
+            _ => println!("unknown command: {line}"),
+        }
+    }
+}
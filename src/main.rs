@@ -1,10 +1,8 @@
 use std::sync::Arc;
 
-mod smart;
-
-use crate::smart::device::{SmartSocket, SmartThermometer};
-use crate::smart::location::{SmartHouse, SmartRoom};
-use crate::smart::report::{BorrowingDeviceInfoProvider, OwningDeviceInfoProvider};
+use lesson_4::smart::device::{SmartSocket, SmartThermometer};
+use lesson_4::smart::location::{SmartHouse, SmartRoom};
+use lesson_4::smart::report::{BorrowingDeviceInfoProvider, OwningDeviceInfoProvider};
 
 fn main() {
     // Инициализация устройств
@@ -1,4 +1,10 @@
 use core::fmt;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::SystemTime;
 
 // Трейт `Named` предоставляет функциональность для получения
 /// имени объекта.
@@ -67,7 +73,277 @@ pub trait Named {
 /// В этом примере структура `SmartPlug` реализует как трейт `Named`,
 /// так и трейт `Pluggable`, что позволяет использовать её в контекстах,
 /// требующих доступа к имени устройства и его функциональности подключения.
-pub trait Pluggable: Named {}
+pub trait Pluggable: Named {
+    /// Возвращает текущую потребляемую мощность устройства в ваттах.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает мощность в ваттах. По умолчанию устройство считается
+    /// не потребляющим энергию и возвращает `0.0`.
+    fn power_usage(&self) -> f64 {
+        0.0
+    }
+
+    /// Возвращает произвольные атрибуты устройства: производителя,
+    /// версию прошивки, заметки о расположении и т.п.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает ссылку на таблицу "ключ - значение". По умолчанию
+    /// таблица пустая.
+    fn metadata(&self) -> &HashMap<String, String> {
+        static EMPTY: OnceLock<HashMap<String, String>> = OnceLock::new();
+        EMPTY.get_or_init(HashMap::new)
+    }
+
+    /// Возвращает строку с текущим состоянием устройства для вывода
+    /// в пользовательском интерфейсе.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// По умолчанию возвращает имя устройства.
+    fn status(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// Возвращает вид устройства, например `socket` или `thermometer`.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// По умолчанию возвращает `device`.
+    fn kind(&self) -> &'static str {
+        "device"
+    }
+
+    /// Возвращает устройство как датчик, если оно реализует `Sensor`.
+    ///
+    /// Позволяет получить `&dyn Sensor` из `dyn Pluggable`. Датчики должны
+    /// переопределить метод и вернуть `Some(self)`.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// По умолчанию возвращает `None`.
+    fn as_sensor(&self) -> Option<&dyn Sensor> {
+        None
+    }
+
+    /// Возвращает краткое описание устройства в свободной форме,
+    /// например `socket Printer (on, 1500W)`.
+    ///
+    /// Используется при выводе `dyn Pluggable` через `Display`.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// По умолчанию возвращает имя устройства.
+    fn describe(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// Включает или выключает устройство.
+    ///
+    /// По умолчанию устройство не поддерживает управление питанием,
+    /// и вызов ничего не делает.
+    fn set_power(&self, _on: bool) {}
+
+    /// Возвращает `true`, если устройство включено.
+    ///
+    /// По умолчанию возвращает `false`.
+    fn is_on(&self) -> bool {
+        false
+    }
+
+    /// Возвращает состояние исправности устройства.
+    ///
+    /// По умолчанию устройство считается исправным.
+    fn health(&self) -> DeviceHealth {
+        DeviceHealth::Ok
+    }
+
+    /// Возвращает время, когда устройство последний раз выходило на связь.
+    ///
+    /// Имеет смысл для сетевых устройств; по устаревшему значению отчеты
+    /// могут определять потерявшие связь устройства. По умолчанию
+    /// возвращает `None`.
+    fn last_seen(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Оценивает стоимость работы устройства в течение `hours` часов при
+    /// цене `price_per_kwh` за киловатт-час.
+    ///
+    /// По умолчанию считает, что устройство все это время потребляет
+    /// текущую мощность `power_usage`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::device::SmartSocket;
+    /// use lesson_4::smart::Pluggable;
+    ///
+    /// let socket = SmartSocket::with_power(String::from("Heater"), 2000.0);
+    /// socket.turn_on();
+    /// assert_eq!(socket.energy_cost(0.25, 2.0), 1.0);
+    /// ```
+    fn energy_cost(&self, price_per_kwh: f64, hours: f64) -> f64 {
+        self.power_usage() / 1000.0 * hours * price_per_kwh
+    }
+
+    /// Возвращает устройство в исходное состояние.
+    ///
+    /// По умолчанию ничего не делает.
+    ///
+    /// Метод требует `&mut self`, поэтому для устройства, подключенного к
+    /// комнате через `Arc<dyn Pluggable>`, его можно вызвать только пока на
+    /// устройство нет других ссылок (через `Arc::get_mut`). Устройство,
+    /// разделяемое несколькими комнатами или удерживаемое вызывающим кодом,
+    /// сбросить так нельзя; для него остаются методы с `&self`, например
+    /// `set_power`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::device::SmartSocket;
+    /// use lesson_4::smart::Pluggable;
+    ///
+    /// let socket = SmartSocket::with_power(String::from("Heater"), 1500.0);
+    /// socket.turn_on();
+    ///
+    /// let mut device: Arc<dyn Pluggable> = Arc::new(socket);
+    /// Arc::get_mut(&mut device).unwrap().reset();
+    /// assert!(!device.is_on());
+    ///
+    /// let shared = Arc::clone(&device);
+    /// assert!(Arc::get_mut(&mut device).is_none());
+    /// # drop(shared);
+    /// ```
+    fn reset(&mut self) {}
+
+    /// Возвращает версию прошивки устройства.
+    ///
+    /// По умолчанию версия неизвестна и метод возвращает `"unknown"`.
+    fn firmware(&self) -> &str {
+        "unknown"
+    }
+
+    /// Возвращает запись устройства для сохранения.
+    ///
+    /// Запись содержит тип устройства, имя и его поля в виде строк. По
+    /// умолчанию поля не сохраняются, и `DeviceRecord::into_device`
+    /// восстанавливает такое устройство как `NullDevice`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::device::NullDevice;
+    /// use lesson_4::smart::Pluggable;
+    ///
+    /// let record = NullDevice::new(String::from("Probe")).serialize_kind();
+    /// assert_eq!(record.kind, "device");
+    /// assert_eq!(record.name, "Probe");
+    /// assert!(record.fields.is_empty());
+    /// ```
+    fn serialize_kind(&self) -> DeviceRecord {
+        DeviceRecord::new(self.kind(), self.name())
+    }
+
+    /// Создает независимую копию устройства.
+    ///
+    /// В отличие от клонирования `Arc<dyn Pluggable>`, копия не разделяет
+    /// состояние с исходным устройством. По умолчанию устройство копирование
+    /// не поддерживает и метод возвращает `None`; устройства, реализующие
+    /// `Clone`, могут переопределить его как `Some(Box::new(self.clone()))`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::device::SmartSocket;
+    /// use lesson_4::smart::Pluggable;
+    ///
+    /// let socket = SmartSocket::with_power(String::from("Kettle"), 2000.0);
+    /// let copy = socket.clone_box().unwrap();
+    ///
+    /// copy.set_power(true);
+    /// assert!(copy.is_on());
+    /// assert!(!socket.is_on());
+    /// ```
+    fn clone_box(&self) -> Option<Box<dyn Pluggable>> {
+        None
+    }
+}
+
+/// Вывод произвольного устройства через `Display`.
+///
+/// Позволяет выводить `Arc<dyn Pluggable>` с помощью `println!`; текст
+/// берется из метода `describe`.
+///
+/// # Пример
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use lesson_4::smart::Pluggable;
+/// use lesson_4::smart::device::SmartSocket;
+///
+/// let socket = SmartSocket::with_power(String::from("Printer"), 1500.0);
+/// socket.turn_on();
+///
+/// let device: Arc<dyn Pluggable> = Arc::new(socket);
+/// assert_eq!(device.to_string(), "socket Printer (on, 1500W)");
+/// ```
+impl fmt::Display for dyn Pluggable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe())
+    }
+}
+
+/// Трейт `Sensor` для устройств, выполняющих измерения.
+///
+/// В отличие от исполнительных устройств, таких как розетки, датчики
+/// сообщают измеренное значение, что позволяет обрабатывать их
+/// единообразно, не зная конкретного типа.
+///
+/// # Пример
+///
+/// ```rust
+/// use lesson_4::smart::device::{Sensor, SmartThermometer, Temperature};
+///
+/// let mut thermometer = SmartThermometer::new(String::from("Kitchen Thermometer"));
+/// thermometer.set_temperature(Temperature::from_celsius(21.5));
+///
+/// let sensor: &dyn Sensor = &thermometer;
+/// assert_eq!(format!("{} {}", sensor.read(), sensor.unit()), "21.5 °C");
+/// ```
+pub trait Sensor: Pluggable {
+    /// Возвращает последнее измеренное значение.
+    fn read(&self) -> f64;
+
+    /// Возвращает обозначение единицы измерения, например `°C`.
+    fn unit(&self) -> &'static str;
+}
+
+/// Состояние исправности устройства.
+///
+/// # Варианты
+///
+/// - `Ok` — устройство исправно.
+/// - `Warning(String)` — устройство работает, но требует внимания.
+/// - `Fault(String)` — устройство неисправно.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceHealth {
+    Ok,
+    Warning(String),
+    Fault(String),
+}
+
+impl fmt::Display for DeviceHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::Warning(reason) => write!(f, "warning: {}", reason),
+            Self::Fault(reason) => write!(f, "fault: {}", reason),
+        }
+    }
+}
 
 /// Представляет умную розетку.
 ///
@@ -77,7 +353,23 @@ pub trait Pluggable: Named {}
 /// # Поля
 ///
 /// - `name`: Строка, представляющая имя умной розетки. Это имя может
-///     быть использовано для идентификации розетки в пользовательском интерфейсе.
+///   быть использовано для идентификации розетки в пользовательском интерфейсе.
+/// - `power`: Мощность подключенной нагрузки в ваттах.
+/// - `on`: Признак того, что розетка включена. Состояние хранится в
+///   `AtomicBool`, поэтому розетку можно включать и выключать, даже когда
+///   она подключена к комнате через `Arc<dyn Pluggable>`.
+/// - `metadata`: Произвольные атрибуты розетки (производитель, прошивка
+///   и т.п.), задаваемые методом `set_metadata`.
+/// - `schedule`: Расписание включения и выключения, применяемое
+///   методом `tick`.
+/// - `history`: Последние `HISTORY_CAPACITY` замеров потребляемой
+///   мощности, записанные методом `record_sample`.
+/// - `power_limit`: Порог мощности, при превышении которого розетка
+///   сообщает о предупреждении в `health`.
+/// - `firmware`: Версия прошивки, установленная методом `upgrade_firmware`.
+/// - `volts`, `amps`: Напряжение и сила тока нагрузки для розеток,
+///   созданных методом `electrical`. Мощность таких розеток равна их
+///   произведению.
 ///
 /// # Примечание
 ///
@@ -93,12 +385,41 @@ pub trait Pluggable: Named {}
 ///     name: String::from("Living Room Socket"),
 /// };
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct SmartSocket {
     name: String,
+    power: f64,
+    on: AtomicBool,
+    metadata: HashMap<String, String>,
+    schedule: Option<Schedule>,
+    history: VecDeque<(SystemTime, f64)>,
+    power_limit: Option<f64>,
+    firmware: Option<String>,
+    volts: Option<f64>,
+    amps: Option<f64>,
+}
+
+impl Clone for SmartSocket {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            power: self.power,
+            on: AtomicBool::new(self.is_on()),
+            metadata: self.metadata.clone(),
+            schedule: self.schedule,
+            history: self.history.clone(),
+            power_limit: self.power_limit,
+            firmware: self.firmware.clone(),
+            volts: self.volts,
+            amps: self.amps,
+        }
+    }
 }
 
 impl SmartSocket {
+    /// Количество замеров мощности, которое хранит розетка.
+    pub const HISTORY_CAPACITY: usize = 64;
+
     /// Создает новый экземпляр `SmartSocket`.
     ///
     /// Этот метод является конструктором для структуры `SmartSocket`,
@@ -122,7 +443,287 @@ impl SmartSocket {
     ///
     /// Возвращает новый экземпляр `SmartSocket` с заданным именем.
     pub fn new(name: String) -> Self {
-        Self { name }
+        Self::with_power(name, 0.0)
+    }
+
+    /// Возвращает построитель розетки с заданным именем.
+    ///
+    /// См. `SmartSocketBuilder`.
+    pub fn builder(name: String) -> SmartSocketBuilder {
+        SmartSocketBuilder::new(name)
+    }
+
+    /// Создает новую выключенную розетку с нагрузкой заданной мощности.
+    ///
+    /// # Аргументы
+    ///
+    /// * `name` - Строка, представляющая имя для умной розетки.
+    /// * `power` - Мощность подключенной нагрузки в ваттах. Розетка
+    ///   потребляет ее только во включенном состоянии.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::Pluggable;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let socket = SmartSocket::with_power(String::from("Heater"), 1500.0);
+    /// assert_eq!(socket.power_usage(), 0.0);
+    ///
+    /// socket.turn_on();
+    /// assert_eq!(socket.power_usage(), 1500.0);
+    /// ```
+    pub fn with_power(name: String, power: f64) -> Self {
+        Self {
+            name,
+            power,
+            on: AtomicBool::new(false),
+            metadata: HashMap::default(),
+            schedule: None,
+            history: VecDeque::with_capacity(Self::HISTORY_CAPACITY),
+            power_limit: None,
+            firmware: None,
+            volts: None,
+            amps: None,
+        }
+    }
+
+    /// Создает новую выключенную розетку, мощность нагрузки которой
+    /// определяется напряжением и силой тока.
+    ///
+    /// # Аргументы
+    ///
+    /// * `name` - Строка, представляющая имя для умной розетки.
+    /// * `volts` - Напряжение в вольтах.
+    /// * `amps` - Сила тока нагрузки в амперах.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::Pluggable;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let socket = SmartSocket::electrical(String::from("Heater"), 230.0, 10.0);
+    /// socket.turn_on();
+    /// assert_eq!(socket.power_usage(), 2300.0);
+    /// assert_eq!(socket.volts(), Some(230.0));
+    /// ```
+    pub fn electrical(name: String, volts: f64, amps: f64) -> Self {
+        Self {
+            volts: Some(volts),
+            amps: Some(amps),
+            ..Self::with_power(name, volts * amps)
+        }
+    }
+
+    /// Возвращает напряжение нагрузки, если розетка создана методом
+    /// `electrical`.
+    pub fn volts(&self) -> Option<f64> {
+        self.volts
+    }
+
+    /// Возвращает силу тока нагрузки, если розетка создана методом
+    /// `electrical`.
+    pub fn amps(&self) -> Option<f64> {
+        self.amps
+    }
+
+    /// Включает розетку.
+    pub fn turn_on(&self) {
+        self.on.store(true, Ordering::Relaxed);
+    }
+
+    /// Выключает розетку.
+    pub fn turn_off(&self) {
+        self.on.store(false, Ordering::Relaxed);
+    }
+
+    /// Возвращает `true`, если розетка включена.
+    pub fn is_on(&self) -> bool {
+        self.on.load(Ordering::Relaxed)
+    }
+
+    /// Записывает текущую потребляемую мощность в историю замеров.
+    ///
+    /// История хранит не более `HISTORY_CAPACITY` замеров; при
+    /// переполнении самый старый замер вытесняется.
+    ///
+    /// # Аргументы
+    ///
+    /// * `now` - Момент времени, к которому относится замер.
+    pub fn record_sample(&mut self, now: SystemTime) {
+        if self.history.len() == Self::HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+
+        self.history.push_back((now, self.power_usage()));
+        self.history.make_contiguous();
+    }
+
+    /// Возвращает историю замеров мощности от старых к новым.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::time::SystemTime;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut socket = SmartSocket::with_power(String::from("Heater"), 1500.0);
+    /// socket.turn_on();
+    /// socket.record_sample(SystemTime::now());
+    ///
+    /// assert_eq!(socket.history().len(), 1);
+    /// assert_eq!(socket.history()[0].1, 1500.0);
+    /// ```
+    pub fn history(&self) -> &[(SystemTime, f64)] {
+        self.history.as_slices().0
+    }
+
+    /// Задает порог потребляемой мощности в ваттах.
+    ///
+    /// Если розетка потребляет больше заданного порога, `health`
+    /// возвращает `DeviceHealth::Warning`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::Pluggable;
+    /// use lesson_4::smart::device::{DeviceHealth, SmartSocket};
+    ///
+    /// let mut socket = SmartSocket::with_power(String::from("Heater"), 2500.0);
+    /// socket.set_power_limit(2000.0);
+    /// assert_eq!(socket.health(), DeviceHealth::Ok);
+    ///
+    /// socket.turn_on();
+    /// assert!(matches!(socket.health(), DeviceHealth::Warning(_)));
+    /// ```
+    pub fn set_power_limit(&mut self, limit: f64) {
+        self.power_limit = Some(limit);
+    }
+
+    /// Имитирует обновление прошивки розетки до версии `version`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::device::SmartSocket;
+    /// use lesson_4::smart::Pluggable;
+    ///
+    /// let mut socket = SmartSocket::new(String::from("Heater"));
+    /// assert_eq!(socket.firmware(), "unknown");
+    ///
+    /// socket.upgrade_firmware(String::from("1.2.0"));
+    /// assert_eq!(socket.firmware(), "1.2.0");
+    /// ```
+    pub fn upgrade_firmware(&mut self, version: String) {
+        self.firmware = Some(version);
+    }
+
+    /// Задает расписание включения и выключения розетки.
+    ///
+    /// Расписание применяется при вызове `tick`.
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = Some(schedule);
+    }
+
+    /// Применяет расписание к моменту времени `now`.
+    ///
+    /// Время передается явно, поэтому поведение розетки можно проверять
+    /// без настоящих часов. Если расписание не задано, состояние розетки
+    /// не меняется.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::time::{Duration, SystemTime};
+    /// use lesson_4::smart::device::{Schedule, SmartSocket};
+    ///
+    /// let now = SystemTime::now();
+    /// let mut socket = SmartSocket::new(String::from("Lamp"));
+    /// socket.set_schedule(Schedule::new(now, now + Duration::from_secs(60)));
+    ///
+    /// socket.tick(now + Duration::from_secs(1));
+    /// assert!(socket.is_on());
+    /// ```
+    pub fn tick(&self, now: SystemTime) {
+        if let Some(on) = self.schedule.and_then(|s| s.state_at(now)) {
+            self.on.store(on, Ordering::Relaxed);
+        }
+    }
+
+    /// Задает значение атрибута розетки.
+    ///
+    /// Если атрибут с таким ключом уже существует, его значение
+    /// будет заменено.
+    ///
+    /// # Аргументы
+    ///
+    /// * `key` - Название атрибута, например `"vendor"`.
+    /// * `value` - Значение атрибута.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::Pluggable;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut socket = SmartSocket::new(String::from("Bedroom Socket"));
+    /// socket.set_metadata(String::from("vendor"), String::from("Acme"));
+    /// assert_eq!(socket.metadata()["vendor"], "Acme");
+    /// ```
+    pub fn set_metadata(&mut self, key: String, value: String) {
+        self.metadata.insert(key, value);
+    }
+}
+
+/// Расписание включения и выключения умной розетки.
+///
+/// Расписание хранит моменты включения (`on_at`) и выключения (`off_at`).
+/// Состояние розетки определяется последним из наступивших событий:
+/// до наступления обоих моментов расписание состояние не меняет.
+///
+/// # Пример
+///
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+/// use lesson_4::smart::device::Schedule;
+///
+/// let on_at = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+/// let schedule = Schedule::new(on_at, on_at + Duration::from_secs(60));
+///
+/// assert_eq!(schedule.state_at(SystemTime::UNIX_EPOCH), None);
+/// assert_eq!(schedule.state_at(on_at), Some(true));
+/// assert_eq!(schedule.state_at(on_at + Duration::from_secs(60)), Some(false));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    on_at: SystemTime,
+    off_at: SystemTime,
+}
+
+impl Schedule {
+    pub fn new(on_at: SystemTime, off_at: SystemTime) -> Self {
+        Self { on_at, off_at }
+    }
+
+    pub fn on_at(&self) -> SystemTime {
+        self.on_at
+    }
+
+    pub fn off_at(&self) -> SystemTime {
+        self.off_at
+    }
+
+    /// Возвращает состояние, которое предписывает расписание в момент
+    /// `now`: `Some(true)` — включено, `Some(false)` — выключено, `None`
+    /// — ни одно событие еще не наступило.
+    pub fn state_at(&self, now: SystemTime) -> Option<bool> {
+        match (now >= self.on_at, now >= self.off_at) {
+            (true, true) => Some(self.on_at > self.off_at),
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        }
     }
 }
 
@@ -185,31 +786,233 @@ impl Named for SmartSocket {
 /// В этом примере `socket` можно использовать как подключаемое
 /// устройство благодаря реализации трейта `Pluggable`, при этом
 /// сохранена возможность доступа к его имени через метод `name`.
-impl Pluggable for SmartSocket {}
+impl Pluggable for SmartSocket {
+    fn clone_box(&self) -> Option<Box<dyn Pluggable>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn serialize_kind(&self) -> DeviceRecord {
+        let mut record = DeviceRecord::new(self.kind(), self.name());
+        record.set("power", self.power);
+        record.set("on", self.is_on());
+
+        if let Some(firmware) = &self.firmware {
+            record.set("firmware", firmware);
+        }
+        if let Some(limit) = self.power_limit {
+            record.set("power_limit", limit);
+        }
+        if let (Some(volts), Some(amps)) = (self.volts, self.amps) {
+            record.set("volts", volts);
+            record.set("amps", amps);
+        }
+        for (key, value) in self.metadata.iter() {
+            record.set(&format!("metadata.{}", key), value);
+        }
+
+        record
+    }
+
+    fn power_usage(&self) -> f64 {
+        match self.is_on() {
+            true => self.power,
+            false => 0.0,
+        }
+    }
+
+    fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    fn status(&self) -> String {
+        let state = match self.is_on() {
+            true => "on",
+            false => "off",
+        };
+
+        format!("Socket[{}]: {}, {}W", self.name(), state, self.power)
+    }
+
+    fn kind(&self) -> &'static str {
+        "socket"
+    }
+
+    fn describe(&self) -> String {
+        let state = match self.is_on() {
+            true => "on",
+            false => "off",
+        };
+
+        format!("socket {} ({}, {}W)", self.name(), state, self.power)
+    }
+
+    fn firmware(&self) -> &str {
+        self.firmware.as_deref().unwrap_or("unknown")
+    }
+
+    /// Выключает розетку и очищает метаданные, расписание и историю
+    /// замеров. Имя, мощность нагрузки, порог мощности и версия прошивки
+    /// сохраняются.
+    fn reset(&mut self) {
+        self.turn_off();
+        self.metadata.clear();
+        self.schedule = None;
+        self.history.clear();
+    }
+
+    fn set_power(&self, on: bool) {
+        match on {
+            true => self.turn_on(),
+            false => self.turn_off(),
+        }
+    }
+
+    fn is_on(&self) -> bool {
+        SmartSocket::is_on(self)
+    }
+
+    fn health(&self) -> DeviceHealth {
+        match self.power_limit {
+            Some(limit) if self.power_usage() > limit => DeviceHealth::Warning(format!(
+                "power {}W exceeds limit {}W",
+                self.power_usage(),
+                limit
+            )),
+            _ => DeviceHealth::Ok,
+        }
+    }
+}
 
 /// Реализация трейта `Display` для структуры `SmartSocket`.
 ///
-/// Эта реализация позволяет выводить представление умной розетки в отчете.
+/// Выводит то же описание, что и `Pluggable::describe`, поэтому розетка
+/// форматируется одинаково независимо от того, доступна ли она напрямую
+/// или через `Arc<dyn Pluggable>`: тип устройства, имя, состояние и
+/// мощность.
 ///
-/// При вызове метода `fmt`, информация о `SmartSocket` будет
-/// отформатирована в строку, содержащую тип устройства и его имя.
+/// Строка отчетов `----> Device: Socket[имя]`, которую раньше выводил
+/// `Display`, формируется самими отчетами (`OwningDeviceInfoProvider`,
+/// `BorrowingDeviceInfoProvider`).
 ///
 /// # Пример
 ///
-/// ```plaintext
-/// // Предполагается, что структура SmartSocket и её реализация метода `name` уже определены.
+/// ```rust
+/// use lesson_4::smart::device::SmartSocket;
 ///
-/// let socket = SmartSocket::new(String::from("Living Room Socket"));
-/// println!("{}", socket);
+/// let socket = SmartSocket::with_power(String::from("Printer"), 1500.0);
+/// socket.turn_on();
+/// assert_eq!(format!("{}", socket), "socket Printer (on, 1500W)");
 /// ```
+impl fmt::Display for SmartSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe())
+    }
+}
+
+/// Построитель умной розетки.
 ///
-/// В результате выполнения этого кода будет выведено:
-/// ```plaintext
-/// ----> Device: Socket[Living Room Socket]
+/// Позволяет задать начальные мощность, состояние и метаданные розетки
+/// при создании, без последовательных вызовов методов-сеттеров.
+///
+/// # Пример
+///
+/// ```rust
+/// use lesson_4::smart::device::SmartSocketBuilder;
+/// use lesson_4::smart::Pluggable;
+///
+/// let socket = SmartSocketBuilder::new(String::from("Heater"))
+///     .power(2000.0)
+///     .initially_on(true)
+///     .metadata(String::from("vendor"), String::from("Acme"))
+///     .build();
+///
+/// assert!(socket.is_on());
+/// assert_eq!(socket.power_usage(), 2000.0);
+/// assert_eq!(socket.metadata()["vendor"], "Acme");
 /// ```
-impl fmt::Display for SmartSocket {
+#[derive(Debug, Clone)]
+pub struct SmartSocketBuilder {
+    name: String,
+    power: f64,
+    on: bool,
+    metadata: HashMap<String, String>,
+}
+
+impl SmartSocketBuilder {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            power: 0.0,
+            on: false,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Задает мощность подключенной нагрузки в ваттах.
+    pub fn power(mut self, watts: f64) -> Self {
+        self.power = watts;
+        self
+    }
+
+    /// Задает начальное состояние розетки.
+    pub fn initially_on(mut self, on: bool) -> Self {
+        self.on = on;
+        self
+    }
+
+    /// Добавляет атрибут в метаданные розетки.
+    pub fn metadata(mut self, key: String, value: String) -> Self {
+        self.metadata.insert(key, value);
+        self
+    }
+
+    /// Создает розетку с накопленными настройками.
+    pub fn build(self) -> SmartSocket {
+        let mut socket = SmartSocket::with_power(self.name, self.power);
+        socket.metadata = self.metadata;
+        socket.set_power(self.on);
+        socket
+    }
+}
+
+/// Температура с явно заданной единицей измерения.
+///
+/// Значение хранится в градусах Цельсия. Создание и чтение температуры
+/// всегда требует указать шкалу, что исключает путаницу между градусами
+/// Цельсия и Фаренгейта.
+///
+/// # Пример
+///
+/// ```rust
+/// use lesson_4::smart::device::Temperature;
+///
+/// let boiling = Temperature::from_celsius(100.0);
+/// assert_eq!(boiling.as_fahrenheit(), 212.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Temperature(f64);
+
+impl Temperature {
+    pub fn from_celsius(celsius: f64) -> Self {
+        Self(celsius)
+    }
+
+    pub fn from_fahrenheit(fahrenheit: f64) -> Self {
+        Self((fahrenheit - 32.0) * 5.0 / 9.0)
+    }
+
+    pub fn as_celsius(&self) -> f64 {
+        self.0
+    }
+
+    pub fn as_fahrenheit(&self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+}
+
+impl fmt::Display for Temperature {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "----> Device: Socket[{}]", self.name())
+        write!(f, "{}°C", self.0)
     }
 }
 
@@ -223,6 +1026,10 @@ impl fmt::Display for SmartSocket {
 ///   идентификации устройства в пользовательском интерфейсе или в системе.
 ///   Например, имя может использоваться для различения между термометрами
 ///   в разных помещениях.
+/// - `temperature`: `Temperature` — последнее измеренное значение
+///   температуры. У нового термометра равно 0 °C.
+/// - `last_seen`: `Option<SystemTime>` — время последнего полученного
+///   замера, записанное методом `update`.
 ///
 /// # Пример
 ///
@@ -240,6 +1047,8 @@ impl fmt::Display for SmartSocket {
 #[derive(Clone)]
 pub struct SmartThermometer {
     name: String,
+    temperature: Temperature,
+    last_seen: Option<SystemTime>,
 }
 
 impl SmartThermometer {
@@ -273,7 +1082,55 @@ impl SmartThermometer {
     /// В этом примере создается новый термометр с именем "Kitchen Thermometer"
     /// и выводится его имя на экран.
     pub fn new(name: String) -> Self {
-        Self { name }
+        Self {
+            name,
+            temperature: Temperature::default(),
+            last_seen: None,
+        }
+    }
+
+    /// Возвращает последнее измеренное значение температуры.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::device::{SmartThermometer, Temperature};
+    ///
+    /// let mut thermometer = SmartThermometer::new(String::from("Kitchen Thermometer"));
+    /// thermometer.set_temperature(Temperature::from_celsius(21.5));
+    /// assert_eq!(thermometer.temperature().as_celsius(), 21.5);
+    /// ```
+    pub fn temperature(&self) -> Temperature {
+        self.temperature
+    }
+
+    /// Сохраняет новое измеренное значение температуры.
+    pub fn set_temperature(&mut self, temperature: Temperature) {
+        self.temperature = temperature;
+    }
+
+    /// Сохраняет замер температуры, полученный от устройства в момент `now`.
+    ///
+    /// В отличие от `set_temperature`, запоминает время замера, которое
+    /// затем возвращает `last_seen`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::time::SystemTime;
+    /// use lesson_4::smart::device::{SmartThermometer, Temperature};
+    /// use lesson_4::smart::Pluggable;
+    ///
+    /// let mut thermometer = SmartThermometer::new(String::from("Kitchen Thermometer"));
+    /// assert!(thermometer.last_seen().is_none());
+    ///
+    /// let now = SystemTime::now();
+    /// thermometer.update(Temperature::from_celsius(21.5), now);
+    /// assert_eq!(thermometer.last_seen(), Some(now));
+    /// ```
+    pub fn update(&mut self, temperature: Temperature, now: SystemTime) {
+        self.temperature = temperature;
+        self.last_seen = Some(now);
     }
 }
 
@@ -337,46 +1194,199 @@ impl Named for SmartThermometer {
 /// В этом примере `termometer` можно использовать как подключаемое
 /// устройство благодаря реализации трейта `Pluggable`, при этом
 /// сохранена возможность доступа к его имени через метод `name`.
-impl Pluggable for SmartThermometer {}
+impl Pluggable for SmartThermometer {
+    fn clone_box(&self) -> Option<Box<dyn Pluggable>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn serialize_kind(&self) -> DeviceRecord {
+        let mut record = DeviceRecord::new(self.kind(), self.name());
+        record.set("temperature", self.temperature.as_celsius());
+        record
+    }
+
+    fn status(&self) -> String {
+        format!("Thermometer[{}]: {}", self.name(), self.temperature)
+    }
+
+    fn kind(&self) -> &'static str {
+        "thermometer"
+    }
+
+    fn as_sensor(&self) -> Option<&dyn Sensor> {
+        Some(self)
+    }
+
+    fn describe(&self) -> String {
+        format!("thermometer {} ({})", self.name(), self.temperature)
+    }
+
+    fn last_seen(&self) -> Option<SystemTime> {
+        self.last_seen
+    }
+}
 
 /// Реализация трейта `fmt::Display` для структуры `SmartThermometer`.
 ///
-/// Этот трейт позволяет формировать строковое представление умного термометра,
-/// что упрощает вывод информации о термометре как в консоль, так и в других
-/// текстовых форматах. Использование `fmt::Display` позволяет выводить
-/// информацию о объекте через стандартные форматы вывода.
+/// Выводит то же описание, что и `Pluggable::describe`, поэтому термометр
+/// форматируется одинаково независимо от того, доступен ли он напрямую
+/// или через `Arc<dyn Pluggable>`: тип устройства, имя и температура.
 ///
-/// # Метод
+/// Строка отчетов `----> Device: Thermometer[имя]`, которую раньше
+/// выводил `Display`, формируется самим `BorrowingDeviceInfoProvider`.
 ///
-/// ## fmt
+/// # Пример
 ///
-/// Этот метод определяет, как именно будет форматироваться
-/// представление `SmartThermometer`. В текущей реализации, метод
-/// возвращает строку, содержащую имя термометра, оформленную в виде:
+/// ```rust
+/// use lesson_4::smart::device::SmartThermometer;
 ///
-/// ```plaintext
-/// ----> Device: Thermometer[имя_термометра]
+/// let thermometer = SmartThermometer::new(String::from("Kitchen Thermometer"));
+/// assert_eq!(format!("{}", thermometer), "thermometer Kitchen Thermometer (0°C)");
 /// ```
+impl fmt::Display for SmartThermometer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe())
+    }
+}
+
+/// Термометр измеряет температуру в градусах Цельсия.
+impl Sensor for SmartThermometer {
+    fn read(&self) -> f64 {
+        self.temperature.as_celsius()
+    }
+
+    fn unit(&self) -> &'static str {
+        "°C"
+    }
+}
+
+/// Пустое устройство, не обладающее никакими возможностями, кроме имени.
 ///
-/// # Пример
+/// Предназначено для тестов и примеров, где важна логика комнат и дома,
+/// а не поведение конкретных устройств. Все методы `Pluggable` используют
+/// реализации по умолчанию.
 ///
-/// Пример использования текущей реализации `fmt::Display`:
+/// # Пример
 ///
 /// ```rust
-/// use lesson_4::smart::device::SmartThermometer;
-/// // Предполагается, что структура SmartThermometer и метод new определены.
-/// let thermometer = SmartThermometer::new(String::from("Kitchen Thermometer"));
-/// println!("{}", thermometer);
+/// use std::sync::Arc;
+/// use lesson_4::smart::device::NullDevice;
+/// use lesson_4::smart::location::SmartRoom;
+///
+/// let mut room = SmartRoom::new(String::from("Lab"));
+/// room.plug(Arc::new(NullDevice::new(String::from("Probe")))).unwrap();
+/// assert_eq!(room.devices(), vec!["Probe".to_string()]);
 /// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullDevice {
+    name: String,
+}
+
+impl NullDevice {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
+
+impl Named for NullDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Pluggable for NullDevice {
+    fn clone_box(&self) -> Option<Box<dyn Pluggable>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Запись устройства для сохранения и восстановления.
 ///
-/// В этом примере мы создаем новый экземпляр `SmartThermometer` и
-/// выводим его в консоль. Вывод будет следующим:
+/// Отделяет формат хранения от трейта `Pluggable`: устройство описывается
+/// тегом типа (`kind`), именем и набором строковых полей. Запись строится
+/// методом `Pluggable::serialize_kind`, а устройство восстанавливается по
+/// тегу методом `into_device`.
 ///
-/// ```plaintext
-/// ----> Device: Thermometer[Kitchen Thermometer]
+/// Сохраняются только параметры устройства, в том числе порог мощности
+/// розетки; расписание, история замеров и время последнего замера в
+/// запись не попадают.
+///
+/// # Пример
+///
+/// ```rust
+/// use lesson_4::smart::device::SmartSocket;
+/// use lesson_4::smart::Pluggable;
+///
+/// let socket = SmartSocket::with_power(String::from("Kettle"), 2000.0);
+/// socket.turn_on();
+///
+/// let restored = socket.serialize_kind().into_device();
+/// assert_eq!(restored.kind(), "socket");
+/// assert_eq!(restored.power_usage(), 2000.0);
 /// ```
-impl fmt::Display for SmartThermometer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "----> Device: Thermometer[{}]", self.name())
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceRecord {
+    pub kind: String,
+    pub name: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl DeviceRecord {
+    /// Создает запись без полей.
+    pub fn new(kind: &str, name: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Сохраняет значение поля в строковом виде.
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.fields.insert(key.to_string(), value.to_string());
+    }
+
+    /// Возвращает значение поля, разобранное в тип `T`.
+    ///
+    /// Возвращает `None`, если поля нет или его значение не разбирается.
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.fields.get(key).and_then(|v| v.parse().ok())
+    }
+
+    /// Восстанавливает устройство по тегу типа.
+    ///
+    /// Теги `"socket"` и `"thermometer"` восстанавливаются как
+    /// `SmartSocket` и `SmartThermometer`; отсутствующие поля получают
+    /// значения по умолчанию. Устройство с любым другим тегом
+    /// восстанавливается как `NullDevice` с тем же именем.
+    pub fn into_device(self) -> Arc<dyn Pluggable> {
+        match self.kind.as_str() {
+            "socket" => {
+                let mut socket =
+                    SmartSocket::with_power(self.name.clone(), self.get("power").unwrap_or(0.0));
+                socket.set_power(self.get("on").unwrap_or(false));
+                socket.firmware = self.get("firmware");
+                socket.power_limit = self.get("power_limit");
+                socket.volts = self.get("volts");
+                socket.amps = self.get("amps");
+
+                for (key, value) in self.fields.iter() {
+                    if let Some(key) = key.strip_prefix("metadata.") {
+                        socket.set_metadata(key.to_string(), value.clone());
+                    }
+                }
+
+                Arc::new(socket)
+            }
+            "thermometer" => {
+                let mut thermometer = SmartThermometer::new(self.name.clone());
+                thermometer.set_temperature(Temperature::from_celsius(
+                    self.get("temperature").unwrap_or(0.0),
+                ));
+
+                Arc::new(thermometer)
+            }
+            _ => Arc::new(NullDevice::new(self.name)),
+        }
     }
 }
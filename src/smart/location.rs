@@ -1,9 +1,259 @@
 use core::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::{error::Error, sync::Arc};
 
+use crate::smart::device::{DeviceHealth, Sensor};
+use crate::smart::report::{make_report, ReportFormat};
 use crate::smart::Pluggable;
 use crate::smart::Reportable;
 
+/// Ошибки, возникающие при работе с умным домом `SmartHouse`.
+///
+/// # Варианты
+///
+/// - `RoomAlreadyExists(String)` — комната с указанным именем уже
+///   добавлена в дом.
+/// - `InvalidName(String)` — имя комнаты пустое или состоит только
+///   из пробельных символов.
+/// - `RoomNotFound(String)` — комната с указанным именем не найдена.
+/// - `Room(SmartRoomError)` — ошибка операции над комнатой дома.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmartHouseError {
+    RoomAlreadyExists(String),
+    InvalidName(String),
+    RoomNotFound(String),
+    Room(SmartRoomError),
+}
+
+impl fmt::Display for SmartHouseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RoomAlreadyExists(name) => write!(f, "room {} already constructed", name),
+            Self::InvalidName(name) => write!(f, "invalid room name {:?}", name),
+            Self::RoomNotFound(name) => write!(f, "room {} not found", name),
+            Self::Room(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SmartHouseError {}
+
+impl From<SmartRoomError> for SmartHouseError {
+    fn from(e: SmartRoomError) -> Self {
+        Self::Room(e)
+    }
+}
+
+/// Ошибки подсчета суммарной мощности дома.
+///
+/// # Варианты
+///
+/// - `InvalidReading { device, value }` — устройство сообщило мощность,
+///   равную `NaN` или бесконечности.
+/// - `Overflow` — сумма корректных значений вышла за пределы `f64`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PowerError {
+    InvalidReading { device: String, value: f64 },
+    Overflow,
+}
+
+impl fmt::Display for PowerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidReading { device, value } => {
+                write!(f, "device {} reported invalid power {}", device, value)
+            }
+            Self::Overflow => write!(f, "total power overflow"),
+        }
+    }
+}
+
+impl Error for PowerError {}
+
+/// Проблема конфигурации дома, найденная `SmartHouse::validate`.
+///
+/// # Варианты
+///
+/// - `EmptyRoom(String)` — ни к комнате, ни к ее вложенным комнатам не
+///   подключено ни одного устройства. Проверяются и сами вложенные комнаты.
+/// - `DuplicateDeviceName { device, rooms }` — разные устройства с одним
+///   и тем же именем подключены к нескольким комнатам. Общие устройства,
+///   подключенные через `plug_shared`, проблемой не считаются.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    EmptyRoom(String),
+    DuplicateDeviceName { device: String, rooms: Vec<String> },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyRoom(room) => write!(f, "room {} has no devices", room),
+            Self::DuplicateDeviceName { device, rooms } => write!(
+                f,
+                "device name {} is used in several rooms: [{}]",
+                device,
+                rooms.join(", ")
+            ),
+        }
+    }
+}
+
+/// Вид изменения состава устройств комнаты.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Plugged,
+    Unplugged,
+}
+
+/// Обработчик изменений, получающий имя комнаты, имя устройства и вид
+/// изменения.
+pub type ChangeCallback = dyn Fn(&str, &str, ChangeKind);
+
+/// Обработчик превышения мощности комнаты, получающий ее суммарную
+/// мощность в ваттах.
+pub type PowerAlertCallback = dyn Fn(f64);
+
+/// Общий для дома и его комнат список подписчиков на изменения.
+type Subscribers = Rc<RefCell<Vec<Box<ChangeCallback>>>>;
+
+/// Выдает уникальный идентификатор для нового экземпляра `SmartHouse`.
+fn next_house_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Индекс устройств дома: имя устройства → позиции комнат дома, к которым
+/// или к вложенным комнатам которых подключено устройство с таким именем.
+///
+/// Обновляется при каждом изменении комнат дома, затрагивая только
+/// измененную комнату. Полностью перестраивается лишь после
+/// `SmartHouse::iter_rooms_mut`, при первом следующем поиске.
+#[derive(Default, Clone)]
+struct DeviceIndex {
+    rooms: HashMap<String, Vec<usize>>,
+    names: Vec<Vec<String>>,
+    stale: bool,
+}
+
+impl DeviceIndex {
+    /// Добавляет в индекс комнату, помещенную в конец списка комнат дома.
+    fn push_room(&mut self, room: &SmartRoom) {
+        let position = self.names.len();
+        let names = Self::names_of(room);
+
+        for name in names.iter() {
+            self.rooms.entry(name.clone()).or_default().push(position);
+        }
+
+        self.names.push(names);
+    }
+
+    /// Убирает из индекса комнату с позицией `position` и сдвигает позиции
+    /// следующих за ней комнат.
+    fn remove_room(&mut self, position: usize) {
+        self.forget(position);
+        self.names.remove(position);
+
+        for positions in self.rooms.values_mut() {
+            for p in positions.iter_mut().filter(|p| **p > position) {
+                *p -= 1;
+            }
+        }
+    }
+
+    /// Обновляет записи комнаты с позицией `position` после ее изменения.
+    fn update_room(&mut self, position: usize, room: &SmartRoom) {
+        self.forget(position);
+
+        let names = Self::names_of(room);
+
+        for name in names.iter() {
+            let positions = self.rooms.entry(name.clone()).or_default();
+
+            if let Err(i) = positions.binary_search(&position) {
+                positions.insert(i, position);
+            }
+        }
+
+        self.names[position] = names;
+    }
+
+    /// Отражает в индексе перестановку комнат с позициями `a` и `b`.
+    fn swap_rooms(&mut self, a: usize, b: usize) {
+        for name in self.names[a].iter().chain(self.names[b].iter()) {
+            if let Some(positions) = self.rooms.get_mut(name) {
+                for p in positions.iter_mut() {
+                    if *p == a {
+                        *p = b;
+                    } else if *p == b {
+                        *p = a;
+                    }
+                }
+
+                positions.sort_unstable();
+                positions.dedup();
+            }
+        }
+
+        self.names.swap(a, b);
+    }
+
+    /// Строит индекс заново по всем комнатам дома.
+    fn rebuild(&mut self, rooms: &[SmartRoom]) {
+        self.rooms.clear();
+        self.names.clear();
+
+        for room in rooms {
+            self.push_room(room);
+        }
+
+        self.stale = false;
+    }
+
+    /// Убирает позицию `position` из записей устройств этой комнаты.
+    fn forget(&mut self, position: usize) {
+        for name in self.names[position].iter() {
+            if let Some(positions) = self.rooms.get_mut(name) {
+                positions.retain(|&p| p != position);
+
+                if positions.is_empty() {
+                    self.rooms.remove(name);
+                }
+            }
+        }
+    }
+
+    /// Возвращает различные имена устройств комнаты и ее вложенных комнат.
+    fn names_of(room: &SmartRoom) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+
+        for device in room.all_devices_arc() {
+            if !names.iter().any(|name| name == device.name()) {
+                names.push(device.name().to_string());
+            }
+        }
+
+        names
+    }
+}
+
+/// Проверяет, что имя не пустое и не состоит только из пробельных символов.
+fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty()
+}
+
+/// Убирает пробелы по краям имени и заменяет серии пробельных символов
+/// внутри него одним пробелом.
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 /// Структура `SmartHouse` представляет собой умный дом, содержащий
 /// информацию о его названии и комнатах.
 ///
@@ -27,6 +277,30 @@ use crate::smart::Reportable;
 ///   экземпляром структуры `SmartRoom`, что позволяет управлять
 ///   ее устройствами и функциональностью.
 ///
+/// - `version`: `u64`
+///
+///   Номер версии дома, который увеличивается при каждом изменении.
+///   Используется для обнаружения изменений, например при кэшировании
+///   отчетов.
+///
+/// - `subscribers`: `Subscribers`
+///
+///   Подписчики на подключение и отключение устройств. Список разделяется
+///   с комнатами дома, чтобы изменения, сделанные напрямую в комнате,
+///   тоже доходили до подписчиков. Копия дома подписчиков не наследует.
+///
+/// - `id`: `u64`
+///
+///   Уникальный идентификатор экземпляра дома. Копия дома получает новый
+///   идентификатор. Используется, чтобы отличать дома с одинаковой
+///   версией, например в `CachedReport`.
+///
+/// - `device_index`: `RefCell<DeviceIndex>`
+///
+///   Индекс для поиска комнаты по имени устройства, включая устройства
+///   вложенных комнат. Обновляется методами, изменяющими комнаты дома, в
+///   том числе при уничтожении `SmartRoomMut`.
+///
 /// # Пример
 ///
 /// ```rust
@@ -38,10 +312,33 @@ use crate::smart::Reportable;
 /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
 /// smart_house.add( living_room );
 /// ```
-#[derive(Clone)]
 pub struct SmartHouse {
     name: String,
     rooms: Vec<SmartRoom>,
+    version: u64,
+    subscribers: Subscribers,
+    id: u64,
+    device_index: RefCell<DeviceIndex>,
+}
+
+impl Clone for SmartHouse {
+    fn clone(&self) -> Self {
+        let subscribers = Subscribers::default();
+        let mut rooms = self.rooms.clone();
+
+        for room in rooms.iter_mut() {
+            room.subscribers = Some(Rc::clone(&subscribers));
+        }
+
+        Self {
+            name: self.name.clone(),
+            rooms,
+            version: self.version,
+            subscribers,
+            id: next_house_id(),
+            device_index: self.device_index.clone(),
+        }
+    }
 }
 
 impl SmartHouse {
@@ -73,9 +370,38 @@ impl SmartHouse {
         Self {
             name,
             rooms: Vec::default(),
+            version: 0,
+            subscribers: Subscribers::default(),
+            id: next_house_id(),
+            device_index: RefCell::default(),
         }
     }
 
+    /// Создает дом сразу с набором комнат.
+    ///
+    /// Комнаты добавляются по порядку методом `add`, поэтому повторяющиеся
+    /// или пустые имена комнат приводят к ошибке.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    ///
+    /// let smart_house = SmartHouse::with_rooms(
+    ///     String::from("My Smart Home"),
+    ///     vec![
+    ///         SmartRoom::new(String::from("Kitchen")),
+    ///         SmartRoom::new(String::from("Bedroom")),
+    ///     ],
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(smart_house.room_names(), vec!["Kitchen", "Bedroom"]);
+    /// ```
+    pub fn with_rooms(name: String, rooms: Vec<SmartRoom>) -> Result<Self, SmartHouseError> {
+        Self::try_from((name, rooms))
+    }
+
     /// Возвращает имя умного дома.
     ///
     /// Этот метод предоставляет доступ к имени экземпляра `SmartHouse`.
@@ -100,11 +426,34 @@ impl SmartHouse {
         &self.name
     }
 
+    /// Переименовывает умный дом.
+    ///
+    /// Метод заменяет название дома на новое. Это удобно, например,
+    /// когда готовая планировка загружается и затем подписывается
+    /// для конкретного жильца.
+    ///
+    /// # Параметры
+    ///
+    /// - `name`: `String` — Новое название умного дома.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::SmartHouse;
+    /// let mut smart_house = SmartHouse::new(String::from("Template"));
+    /// smart_house.set_name(String::from("Flat 42"));
+    /// assert_eq!(smart_house.name(), "Flat 42");
+    /// ```
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+        self.touch();
+    }
+
     /// Добавляет новую умную комнату в дом.
     ///
     /// Этот метод позволяет добавлять экземпляр `SmartRoom` в вектор
-    /// комнат умного дома. Перед добавлением метода проверяет, существует
-    /// ли уже комната с таким же именем. Если комната уже существует,
+    /// комнат умного дома. Перед добавлением метода проверяет, что имя
+    /// комнаты не пустое и что комнаты с таким же именем еще нет. Иначе
     /// метод возвращает ошибку.
     ///
     /// # Параметры
@@ -116,8 +465,10 @@ impl SmartHouse {
     ///
     /// Возвращает:
     /// - `Ok(())` — Если комната успешно добавлена.
-    /// - `Err(Box<dyn Error>)` — Если комната с таким же именем
-    ///   уже существует.
+    /// - `Err(SmartHouseError::RoomAlreadyExists)` — Если комната с таким
+    ///   же именем уже существует.
+    /// - `Err(SmartHouseError::InvalidName)` — Если имя комнаты пустое или
+    ///   состоит только из пробельных символов.
     ///
     /// # Пример
     ///
@@ -139,144 +490,1524 @@ impl SmartHouse {
     ///     Err(e) => println!("Error adding room: {}", e),
     /// }
     /// ```
-    pub fn add(&mut self, room: SmartRoom) -> Result<(), Box<dyn Error>> {
+    pub fn add(&mut self, mut room: SmartRoom) -> Result<(), SmartHouseError> {
+        if !is_valid_name(room.name()) {
+            return Err(SmartHouseError::InvalidName(room.name().to_string()));
+        }
+
         match self.get_rooms().iter().find(|&v| v.name() == room.name()) {
-            Some(_) => Err(format!("room {} already constructed", room.name()).into()),
+            Some(_) => Err(SmartHouseError::RoomAlreadyExists(room.name().to_string())),
             None => {
+                room.subscribers = Some(Rc::clone(&self.subscribers));
+                self.device_index.get_mut().push_room(&room);
                 self.rooms.push(room);
+                self.touch();
 
                 Ok(())
             }
         }
     }
 
-    #[allow(dead_code)]
-    pub fn del(&mut self, room: &str) {
-        if let Some(index) = self.get_rooms().iter().position(|r| r.name() == room) {
-            self.rooms.remove(index);
+    /// Добавляет комнату, если комнаты с таким именем еще нет.
+    ///
+    /// В отличие от `add`, повторное добавление не считается ошибкой:
+    /// существующая комната сохраняется, а переданная отбрасывается.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `true` — Если комната добавлена.
+    /// - `false` — Если комната с таким именем уже есть в доме, а также
+    ///   если имя комнаты пустое или состоит только из пробельных символов:
+    ///   такой комнаты в доме нет, и она не добавляется. Чтобы отличить
+    ///   этот случай, используйте `add`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    ///
+    /// assert!(smart_house.ensure_room(SmartRoom::new(String::from("Kitchen"))));
+    /// assert!(!smart_house.ensure_room(SmartRoom::new(String::from("Kitchen"))));
+    /// assert!(!smart_house.ensure_room(SmartRoom::new(String::from(" "))));
+    /// assert_eq!(smart_house.room_names(), vec!["Kitchen"]);
+    /// ```
+    pub fn ensure_room(&mut self, room: SmartRoom) -> bool {
+        self.add(room).is_ok()
+    }
+
+    /// Строит новый дом, преобразуя каждую комнату функцией `f`.
+    ///
+    /// Комнаты обрабатываются в порядке добавления и добавляются в новый дом
+    /// методом `add`, поэтому повторяющиеся или пустые имена, появившиеся в
+    /// результате преобразования, приводят к ошибке. Подписчики дома
+    /// переходят в новый дом.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// let result = smart_house.map_rooms(|_| SmartRoom::new(String::from("Hall")));
+    /// assert!(result.is_err());
+    /// ```
+    pub fn map_rooms<F: Fn(SmartRoom) -> SmartRoom>(
+        self,
+        f: F,
+    ) -> Result<SmartHouse, SmartHouseError> {
+        let SmartHouse {
+            name,
+            rooms,
+            version,
+            subscribers,
+            ..
+        } = self;
+
+        let mut house = SmartHouse {
+            name,
+            rooms: Vec::with_capacity(rooms.len()),
+            version,
+            subscribers,
+            id: next_house_id(),
+            device_index: RefCell::default(),
+        };
+
+        for room in rooms {
+            house.add(f(room))?;
         }
+
+        Ok(house)
     }
 
-    /// Возвращает срез всех умных комнат в доме.
+    /// Возвращает новый дом с тем же именем, содержащий копии комнат,
+    /// удовлетворяющих условию `f`.
     ///
-    /// Этот метод предоставляет доступ к вектору комнат, которые находятся в
-    /// экземпляре `SmartHouse`. Он возвращает ссылку на срез `&[SmartRoom]`,
-    /// позволяя получить список всех комнат, не изменяя внутреннее состояние
-    /// структуры.
+    /// Копии комнат разделяют устройства с исходным домом, но не его
+    /// подписчиков.
     ///
-    /// # Возвращаемое значение
+    /// # Пример
     ///
-    /// Возвращает срез `&[SmartRoom]`, который содержит все комнаты,
-    /// добавленные в умный дом. Пользователь может итерироваться по этим
-    /// комнатам или использовать их для других целей, но не может вносить
-    /// изменения в сами комнаты через этот метод.
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// let kitchen_only = smart_house.filter_rooms(|room| room.name() == "Kitchen");
+    /// assert_eq!(kitchen_only.room_names(), vec!["Kitchen"]);
+    /// ```
+    pub fn filter_rooms<F: Fn(&SmartRoom) -> bool>(&self, f: F) -> SmartHouse {
+        let mut house = SmartHouse::new(self.name.clone());
+
+        for room in self.rooms.iter().filter(|room| f(room)) {
+            let mut room = room.clone();
+            room.subscribers = Some(Rc::clone(&house.subscribers));
+            house.device_index.get_mut().push_room(&room);
+            house.rooms.push(room);
+        }
+
+        house
+    }
+
+    /// Возвращает количество комнат, удовлетворяющих условию `f`.
     ///
     /// # Пример
     ///
     /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::device::SmartSocket;
     /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
     /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
-    /// smart_house.add(SmartRoom::new(String::from("Living Room"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
     /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    /// smart_house
+    ///     .plug("Kitchen", Arc::new(SmartSocket::new(String::from("Kettle"))))
+    ///     .unwrap();
     ///
-    /// // Получение списка комнат
-    /// for room in smart_house.get_rooms() {
-    ///     println!("Room: {}", room.name());
-    /// }
+    /// assert_eq!(smart_house.count_rooms_where(|room| room.devices().is_empty()), 1);
     /// ```
-    pub fn get_rooms(&self) -> &[SmartRoom] {
-        &self.rooms
+    pub fn count_rooms_where<F: Fn(&SmartRoom) -> bool>(&self, f: F) -> usize {
+        self.rooms.iter().filter(|room| f(room)).count()
     }
-    /// Создает отчет на основе заданного типа отчета.
+
+    /// Подключает устройство к комнате дома.
     ///
-    /// Этот метод принимает объект, реализующий трейт `Reportable`, и вызывает
-    /// метод `make`, чтобы сгенерировать отчет, используя данные из текущего
-    /// экземпляра `SmartHouse`. Это позволяет разделить логику создания отчетов
-    /// на разные структуры, реализующие данный трейт.
+    /// Позволяет не получать комнату через `get_room_mut` ради одного
+    /// подключения.
     ///
     /// # Параметры
     ///
-    /// - `report`: `T` — Объект, реализующий трейт `Reportable`. Этот объект
-    ///   содержит логику для генерации отчета на основе данных умного дома.
+    /// - `room`: `&str` — Имя комнаты.
+    /// - `device`: `Arc<dyn Pluggable>` — Подключаемое устройство.
     ///
     /// # Возвращаемое значение
     ///
     /// Возвращает:
-    /// - `Ok(String)` — Если отчет успешно создан. Возвращает строку,
-    ///   представляющую сгенерированный отчет.
-    /// - `Err(Box<dyn Error>)` — Если произошла ошибка в процессе создания отчета.
+    /// - `Ok(())` — Если устройство подключено.
+    /// - `Err(SmartHouseError::RoomNotFound)` — Если комната не найдена.
+    /// - `Err(SmartHouseError::Room)` — Если комната отказалась принять
+    ///   устройство, например из-за совпадения имен.
     ///
     /// # Пример
     ///
     /// ```rust
-    /// use std::error::Error;
-    /// use lesson_4::smart::report::Reportable;
-    /// use lesson_4::smart::location::SmartHouse;
-    ///
-    /// struct SimpleReport;
-    ///
-    /// impl Reportable for SimpleReport {
-    ///     fn make(&self, smart_house: &SmartHouse) -> Result<String, Box<dyn Error>> {
-    ///         Ok(format!("Report for: {}", smart_house.name()))
-    ///     }
-    /// }
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
     ///
-    /// let smart_house = SmartHouse::new(String::from("My Smart Home"));
-    /// let report = SimpleReport;
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Office"))).unwrap();
     ///
-    /// match smart_house.create_report(report) {
-    ///     Ok(report_text) => println!("{}", report_text),
-    ///     Err(e) => println!("Error creating report: {}", e),
-    /// }
+    /// smart_house.plug("Office", Arc::new(SmartSocket::new(String::from("Printer")))).unwrap();
+    /// assert!(smart_house.plug("Hall", Arc::new(SmartSocket::new(String::from("Lamp")))).is_err());
     /// ```
-    pub fn create_report<T: Reportable>(&self, report: T) -> Result<String, Box<dyn Error>> {
-        report.make(self)
-    }
-}
+    pub fn plug(&mut self, room: &str, device: Arc<dyn Pluggable>) -> Result<(), SmartHouseError> {
+        let index = self
+            .room_index(room)
+            .ok_or_else(|| SmartHouseError::RoomNotFound(room.to_string()))?;
 
-impl PartialEq for SmartRoom {
-    fn eq(&self, other: &Self) -> bool {
-        self.name() == other.name()
+        self.rooms[index].plug(device)?;
+        self.device_index
+            .get_mut()
+            .update_room(index, &self.rooms[index]);
+        self.touch();
+
+        Ok(())
     }
-}
 
-impl fmt::Display for SmartHouse {
-    /// Форматирует представление умного дома для вывода в строку.
+    /// Подключает одно и то же устройство сразу к нескольким комнатам.
     ///
-    /// Этот метод реализует трейты `fmt::Display`, позволяя
-    /// выводить экземпляр `SmartHouse` в удобочитаемом формате.
-    /// Метод записывает имя умного дома в строковом формате,
-    /// предшествуемое стрелкой (`->`).
+    /// Все комнаты получают один и тот же указатель `Arc`, поэтому
+    /// устройство остается единым физическим устройством, видимым из
+    /// нескольких комнат. Перед подключением проверяются все комнаты:
+    /// если хотя бы одна проверка не прошла, дом остается без изменений.
     ///
     /// # Параметры
     ///
-    /// - `f`: `&mut std::fmt::Formatter` — Мутируемая ссылка на форматтер,
-    ///   который используется для записи форматированной строки.
+    /// - `rooms`: `&[&str]` — Имена комнат, к которым нужно подключить
+    ///   устройство.
+    /// - `device`: `Arc<dyn Pluggable>` — Подключаемое устройство.
     ///
     /// # Возвращаемое значение
     ///
     /// Возвращает:
-    /// - `std::fmt::Result` — Результат выполнения форматирования.
-    ///   Если форматирование прошло успешно, возвращается `Ok(())`,
-    ///   в противном случае возвращается ошибка формата.
+    /// - `Ok(())` — Если устройство подключено ко всем комнатам.
+    /// - `Err(SmartHouseError::RoomNotFound)` — Если комната не найдена.
+    /// - `Err(SmartHouseError::Room)` — Если комната отказалась принять
+    ///   устройство, например из-за совпадения имен.
     ///
     /// # Пример
     ///
     /// ```rust
-    /// use lesson_4::smart::location::SmartHouse;
-    /// let smart_house = SmartHouse::new(String::from("My Smart Home"));
-    /// println!("{}", smart_house);
-    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::Pluggable;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
     ///
-    /// Выход:
-    /// ```plaintext
-    /// -> House: My Smart Home
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Office"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Hall"))).unwrap();
+    ///
+    /// let printer: Arc<dyn Pluggable> = Arc::new(SmartSocket::new(String::from("Printer")));
+    /// smart_house.plug_shared(&["Office", "Hall"], printer.clone()).unwrap();
+    /// assert_eq!(smart_house.shared_count(&printer), 2);
     /// ```
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    pub fn plug_shared(
+        &mut self,
+        rooms: &[&str],
+        device: Arc<dyn Pluggable>,
+    ) -> Result<(), SmartHouseError> {
+        let mut indexes: Vec<usize> = Vec::with_capacity(rooms.len());
+
+        for &name in rooms {
+            let index = self
+                .room_index(name)
+                .ok_or_else(|| SmartHouseError::RoomNotFound(name.to_string()))?;
+
+            if indexes.contains(&index) {
+                continue;
+            }
+
+            if self.rooms[index].is_connected(&*device) {
+                return Err(SmartRoomError::DeviceAlreadyPlugged(device.name().to_string()).into());
+            }
+
+            indexes.push(index);
+        }
+
+        for index in indexes {
+            self.rooms[index].plug(Arc::clone(&device))?;
+            self.device_index
+                .get_mut()
+                .update_room(index, &self.rooms[index]);
+        }
+
+        self.touch();
+
+        Ok(())
+    }
+
+    /// Возвращает количество комнат, к которым подключен именно этот
+    /// экземпляр устройства.
+    ///
+    /// Устройства сравниваются по указателю `Arc`, а не по имени.
+    pub fn shared_count(&self, device: &Arc<dyn Pluggable>) -> usize {
+        self.rooms
+            .iter()
+            .filter(|room| room.devices.iter().any(|d| Arc::ptr_eq(d, device)))
+            .count()
+    }
+
+    /// Перебирает все устройства дома вместе с именами их комнат.
+    ///
+    /// Комнаты и устройства перебираются в порядке добавления; устройства
+    /// вложенных комнат идут сразу после устройств родительской комнаты с
+    /// именем вложенной комнаты. Общее устройство встречается по разу для
+    /// каждой своей комнаты.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.plug("Kitchen", Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///
+    /// for (room, device) in smart_house.all_devices_with_room() {
+    ///     assert_eq!((room, device.name()), ("Kitchen", "Kettle"));
+    /// }
+    /// ```
+    pub fn all_devices_with_room(&self) -> impl Iterator<Item = (&str, &Arc<dyn Pluggable>)> + '_ {
+        self.walk_rooms()
+            .into_iter()
+            .flat_map(|(_, room)| room.devices.iter().map(move |device| (room.name(), device)))
+    }
+
+    /// Возвращает все комнаты дома вместе с вложенными в порядке обхода в
+    /// глубину и уровень вложенности каждой (у комнат дома — 0).
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut bedroom = SmartRoom::new(String::from("Bedroom"));
+    /// bedroom.add_subroom(SmartRoom::new(String::from("Closet"))).unwrap();
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(bedroom).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// let rooms: Vec<(usize, &str)> = smart_house
+    ///     .walk_rooms()
+    ///     .into_iter()
+    ///     .map(|(depth, room)| (depth, room.name()))
+    ///     .collect();
+    /// assert_eq!(rooms, vec![(0, "Bedroom"), (1, "Closet"), (0, "Kitchen")]);
+    /// ```
+    pub fn walk_rooms(&self) -> Vec<(usize, &SmartRoom)> {
+        self.rooms.iter().flat_map(|room| room.walk()).collect()
+    }
+
+    /// Возвращает количество подключений устройств во всех комнатах дома,
+    /// включая вложенные.
+    ///
+    /// Общее устройство учитывается по разу для каждой своей комнаты.
+    pub fn device_count(&self) -> usize {
+        self.walk_rooms()
+            .iter()
+            .map(|(_, room)| room.devices.len())
+            .sum()
+    }
+
+    /// Возвращает плоскую опись устройств дома: комнату, имя, вид и текущую
+    /// мощность каждого устройства.
+    ///
+    /// Записи следуют в порядке `all_devices_with_room`, поэтому их
+    /// количество совпадает с `device_count`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.plug("Kitchen", Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///
+    /// let inventory = smart_house.inventory();
+    /// assert_eq!(inventory[0].kind, "socket");
+    /// ```
+    pub fn inventory(&self) -> Vec<DeviceInventoryEntry> {
+        self.all_devices_with_room()
+            .map(|(room, device)| DeviceInventoryEntry {
+                room: room.to_string(),
+                name: device.name().to_string(),
+                kind: device.kind(),
+                power: device.power_usage(),
+            })
+            .collect()
+    }
+
+    /// Возвращает все различные устройства дома вместе с именами комнат,
+    /// к которым они подключены.
+    ///
+    /// Устройство, подключенное к нескольким комнатам через `plug_shared`,
+    /// встречается в результате один раз. Порядок соответствует первому
+    /// появлению устройства при обходе комнат. Устройства вложенных комнат
+    /// учитываются с именем вложенной комнаты сразу после устройств
+    /// родительской.
+    pub fn distinct_devices(&self) -> Vec<(Arc<dyn Pluggable>, Vec<&str>)> {
+        let mut out: Vec<(Arc<dyn Pluggable>, Vec<&str>)> = Vec::new();
+
+        for (_, room) in self.walk_rooms() {
+            for device in room.devices.iter() {
+                match out.iter_mut().find(|(d, _)| Arc::ptr_eq(d, device)) {
+                    Some((_, rooms)) => rooms.push(room.name()),
+                    None => out.push((Arc::clone(device), vec![room.name()])),
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Проверяет конфигурацию дома перед использованием.
+    ///
+    /// Возвращает список найденных проблем: пустые комнаты и имена, под
+    /// которыми в разных комнатах подключены разные устройства. Пустой
+    /// список означает, что проблем не найдено.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom, ValidationIssue};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// assert_eq!(
+    ///     smart_house.validate(),
+    ///     vec![ValidationIssue::EmptyRoom(String::from("Kitchen"))]
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues: Vec<ValidationIssue> = self
+            .walk_rooms()
+            .into_iter()
+            .filter(|(_, room)| room.all_devices().is_empty())
+            .map(|(_, room)| ValidationIssue::EmptyRoom(room.name().to_string()))
+            .collect();
+
+        let devices = self.distinct_devices();
+        let mut reported: Vec<&str> = Vec::new();
+
+        for (i, (device, _)) in devices.iter().enumerate() {
+            let name = device.name();
+            if reported.contains(&name) {
+                continue;
+            }
+
+            let same: Vec<&(Arc<dyn Pluggable>, Vec<&str>)> = devices[i..]
+                .iter()
+                .filter(|(d, _)| d.name() == name)
+                .collect();
+
+            if same.len() > 1 {
+                reported.push(name);
+
+                let mut rooms: Vec<String> = Vec::new();
+                for room in same.iter().flat_map(|(_, rooms)| rooms.iter()) {
+                    if !rooms.iter().any(|r| r == room) {
+                        rooms.push(room.to_string());
+                    }
+                }
+
+                issues.push(ValidationIssue::DuplicateDeviceName {
+                    device: name.to_string(),
+                    rooms,
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Возвращает позицию комнаты с заданным именем в порядке добавления.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// assert_eq!(smart_house.room_index("Kitchen"), Some(0));
+    /// assert_eq!(smart_house.room_index("Bedroom"), None);
+    /// ```
+    pub fn room_index(&self, name: &str) -> Option<usize> {
+        self.rooms.iter().position(|r| r.name() == name)
+    }
+
+    /// Удаляет комнату из дома.
+    ///
+    /// Удаленная комната возвращается вместе со всеми подключенными
+    /// устройствами, поэтому ее можно изучить или перенести в другой дом.
+    ///
+    /// # Параметры
+    ///
+    /// - `room`: `&str` — Имя удаляемой комнаты.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(SmartRoom)` — Удаленная комната.
+    /// - `Err(SmartHouseError::RoomNotFound)` — Если комнаты с таким
+    ///   именем нет в доме.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// let kitchen = smart_house.del("Kitchen").unwrap();
+    /// assert_eq!(kitchen.name(), "Kitchen");
+    /// assert!(smart_house.del("Kitchen").is_err());
+    /// ```
+    pub fn del(&mut self, room: &str) -> Result<SmartRoom, SmartHouseError> {
+        let index = self
+            .room_index(room)
+            .ok_or_else(|| SmartHouseError::RoomNotFound(room.to_string()))?;
+
+        let mut removed = self.rooms.remove(index);
+        removed.subscribers = None;
+        self.device_index.get_mut().remove_room(index);
+        self.touch();
+
+        Ok(removed)
+    }
+
+    /// Извлекает комнату из дома.
+    ///
+    /// Работает так же, как `del`, но возвращает `None`, если комнаты нет.
+    /// Подключенные устройства остаются в извлеченной комнате, поэтому ее
+    /// можно добавить в другой дом.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut old_house = SmartHouse::new(String::from("Old Home"));
+    /// let mut new_house = SmartHouse::new(String::from("New Home"));
+    /// old_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// let kitchen = old_house.take_room("Kitchen").unwrap();
+    /// new_house.add(kitchen).unwrap();
+    /// assert!(old_house.take_room("Kitchen").is_none());
+    /// ```
+    pub fn take_room(&mut self, name: &str) -> Option<SmartRoom> {
+        self.del(name).ok()
+    }
+
+    /// Меняет местами две комнаты дома.
+    ///
+    /// Комнаты хранятся в порядке добавления; метод позволяет изменить этот
+    /// порядок, например, для отображения в интерфейсе.
+    ///
+    /// # Параметры
+    ///
+    /// - `a`: `&str` — Имя первой комнаты.
+    /// - `b`: `&str` — Имя второй комнаты.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(())` — Если комнаты поменялись местами.
+    /// - `Err(SmartHouseError::RoomNotFound)` — Если любой из комнат нет в
+    ///   доме. Порядок комнат при этом не меняется.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// smart_house.swap_rooms("Kitchen", "Bedroom").unwrap();
+    /// assert_eq!(smart_house.room_names(), vec!["Bedroom", "Kitchen"]);
+    /// ```
+    pub fn swap_rooms(&mut self, a: &str, b: &str) -> Result<(), SmartHouseError> {
+        let first = self
+            .room_index(a)
+            .ok_or_else(|| SmartHouseError::RoomNotFound(a.to_string()))?;
+        let second = self
+            .room_index(b)
+            .ok_or_else(|| SmartHouseError::RoomNotFound(b.to_string()))?;
+
+        self.rooms.swap(first, second);
+        self.device_index.get_mut().swap_rooms(first, second);
+        self.touch();
+
+        Ok(())
+    }
+
+    /// Добавляет префикс к именам всех комнат дома.
+    ///
+    /// Перед переименованием метод проверяет, что новые имена не совпадают
+    /// между собой. При уникальных исходных именах совпадений быть не
+    /// может, но при ошибке ни одна комната не переименовывается.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(())` — Если все комнаты переименованы.
+    /// - `Err(SmartHouseError::RoomAlreadyExists)` — Если после добавления
+    ///   префикса имена комнат совпали.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// smart_house.prefix_room_names("A/").unwrap();
+    /// assert_eq!(smart_house.room_names(), vec!["A/Kitchen", "A/Bedroom"]);
+    /// ```
+    pub fn prefix_room_names(&mut self, prefix: &str) -> Result<(), SmartHouseError> {
+        let names: Vec<String> = self
+            .rooms
+            .iter()
+            .map(|room| format!("{}{}", prefix, room.name()))
+            .collect();
+
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(SmartHouseError::RoomAlreadyExists(name.clone()));
+            }
+        }
+
+        for (room, name) in self.rooms.iter_mut().zip(names) {
+            room.name = name;
+        }
+        self.touch();
+
+        Ok(())
+    }
+
+    /// Подписывается на подключение и отключение устройств во всех
+    /// комнатах дома.
+    ///
+    /// Обработчик вызывается с именем комнаты, именем устройства и видом
+    /// изменения при любом подключении или отключении устройства в комнате,
+    /// принадлежащей дому, независимо от того, как была получена комната:
+    /// через `plug_shared`, `get_room_mut` или `iter_rooms_mut`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// let changes = Rc::new(Cell::new(0));
+    /// let counter = Rc::clone(&changes);
+    /// smart_house.subscribe(Box::new(move |_, _, _| counter.set(counter.get() + 1)));
+    ///
+    /// if let Some(mut kitchen) = smart_house.get_room_mut("Kitchen") {
+    ///     kitchen.plug(Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///     kitchen.unplug("Kettle");
+    /// }
+    ///
+    /// assert_eq!(changes.get(), 2);
+    /// ```
+    pub fn subscribe(&mut self, cb: Box<ChangeCallback>) {
+        self.subscribers.borrow_mut().push(cb);
+    }
+
+    /// Выключает все устройства дома.
+    ///
+    /// Для каждого устройства во всех комнатах вызывается
+    /// `Pluggable::set_power(false)`. Устройства без управления питанием
+    /// пропускаются.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let socket = Arc::new(SmartSocket::with_power(String::from("Heater"), 1500.0));
+    /// socket.turn_on();
+    ///
+    /// let mut room = SmartRoom::new(String::from("Hall"));
+    /// room.plug(socket.clone()).unwrap();
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(room).unwrap();
+    ///
+    /// smart_house.all_off();
+    /// assert!(!socket.is_on());
+    /// ```
+    pub fn all_off(&mut self) {
+        for (_, room) in self.walk_rooms() {
+            for device in room.devices.iter() {
+                device.set_power(false);
+            }
+        }
+
+        self.touch();
+    }
+
+    /// Возвращает суммарную мощность всех устройств дома в ваттах,
+    /// проверяя показания каждого устройства.
+    ///
+    /// Устройство, подключенное к нескольким комнатам через `plug_shared`,
+    /// учитывается один раз.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(f64)` — Суммарная мощность.
+    /// - `Err(PowerError::InvalidReading)` — Если устройство сообщило `NaN`
+    ///   или бесконечную мощность.
+    /// - `Err(PowerError::Overflow)` — Если сумма вышла за пределы `f64`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// let kettle = Arc::new(SmartSocket::with_power(String::from("Kettle"), 2000.0));
+    /// kettle.turn_on();
+    /// room.plug(kettle).unwrap();
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(room).unwrap();
+    /// assert_eq!(smart_house.try_total_power(), Ok(2000.0));
+    /// ```
+    pub fn try_total_power(&self) -> Result<f64, PowerError> {
+        let mut total = 0.0;
+
+        for (device, _) in self.distinct_devices() {
+            let value = device.power_usage();
+
+            if !value.is_finite() {
+                return Err(PowerError::InvalidReading {
+                    device: device.name().to_string(),
+                    value,
+                });
+            }
+
+            total += value;
+        }
+
+        match total.is_finite() {
+            true => Ok(total),
+            false => Err(PowerError::Overflow),
+        }
+    }
+
+    /// Возвращает количество включенных устройств дома.
+    ///
+    /// Устройство, подключенное к нескольким комнатам через `plug_shared`,
+    /// учитывается один раз.
+    pub fn active_count(&self) -> usize {
+        self.distinct_devices()
+            .iter()
+            .filter(|(d, _)| d.is_on())
+            .count()
+    }
+
+    /// Возвращает текущую версию дома.
+    ///
+    /// Версия монотонно возрастает при каждом изменении структуры дома:
+    /// переименовании, добавлении и удалении комнат, подключении и
+    /// отключении устройств. Неизменная версия означает, что не изменился
+    /// состав дома, но не состояние устройств: включение розетки через
+    /// общий `Arc` (`turn_on`, `set_power`, `tick`) или новый замер
+    /// термометра версию не меняют. Версии разных домов независимы и
+    /// могут совпадать.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// let before = smart_house.version();
+    ///
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// assert!(smart_house.version() > before);
+    /// ```
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Возвращает уникальный идентификатор экземпляра дома.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Отмечает изменение дома, увеличивая его версию.
+    fn touch(&mut self) {
+        self.version += 1;
+    }
+
+    /// Возвращает срез всех умных комнат в доме.
+    ///
+    /// Этот метод предоставляет доступ к вектору комнат, которые находятся в
+    /// экземпляре `SmartHouse`. Он возвращает ссылку на срез `&[SmartRoom]`,
+    /// позволяя получить список всех комнат, не изменяя внутреннее состояние
+    /// структуры.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает срез `&[SmartRoom]`, который содержит все комнаты,
+    /// добавленные в умный дом. Пользователь может итерироваться по этим
+    /// комнатам или использовать их для других целей, но не может вносить
+    /// изменения в сами комнаты через этот метод.
+    ///
+    /// Комнаты всегда следуют в порядке добавления в дом (если он не был
+    /// изменен методом `swap_rooms`), поэтому отчеты, построенные по одному
+    /// и тому же дому, воспроизводимы.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Living Room"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// // Получение списка комнат
+    /// for room in smart_house.get_rooms() {
+    ///     println!("Room: {}", room.name());
+    /// }
+    /// ```
+    pub fn get_rooms(&self) -> &[SmartRoom] {
+        &self.rooms
+    }
+
+    /// Возвращает имена комнат дома в порядке их добавления.
+    ///
+    /// Имена возвращаются как ссылки, сами комнаты не клонируются.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Living Room"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// assert_eq!(smart_house.room_names(), vec!["Living Room", "Bedroom"]);
+    /// ```
+    pub fn room_names(&self) -> Vec<&str> {
+        self.rooms.iter().map(|r| r.name()).collect()
+    }
+
+    /// Возвращает итератор с изменяемым доступом ко всем комнатам дома.
+    ///
+    /// Позволяет выполнять пакетные операции над комнатами без их
+    /// клонирования. Так как отследить изменения отдельных комнат нельзя,
+    /// версия дома увеличивается при каждом вызове метода, а индекс
+    /// устройств перестраивается при следующем поиске.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// for room in smart_house.iter_rooms_mut() {
+    ///     room.unplug("Kettle");
+    /// }
+    /// ```
+    pub fn iter_rooms_mut(&mut self) -> impl Iterator<Item = &mut SmartRoom> {
+        self.touch();
+        self.device_index.get_mut().stale = true;
+        self.rooms.iter_mut()
+    }
+
+    /// Возвращает изменяемый доступ к комнате с заданным именем.
+    ///
+    /// Комната выдается через охранный объект `SmartRoomMut`. Если через
+    /// него комната была изменена, при его уничтожении увеличивается
+    /// версия дома (`version`).
+    ///
+    /// # Параметры
+    ///
+    /// - `name`: `&str` — Имя комнаты.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает `Some(SmartRoomMut)`, если комната найдена, иначе `None`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// let before = smart_house.version();
+    ///
+    /// if let Some(mut kitchen) = smart_house.get_room_mut("Kitchen") {
+    ///     kitchen.plug(Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    /// }
+    ///
+    /// assert!(smart_house.version() > before);
+    /// ```
+    pub fn get_room_mut(&mut self, name: &str) -> Option<SmartRoomMut<'_>> {
+        let index = self.room_index(name)?;
+
+        Some(SmartRoomMut {
+            room: &mut self.rooms[index],
+            version: &mut self.version,
+            device_index: self.device_index.get_mut(),
+            position: index,
+            changed: false,
+        })
+    }
+
+    /// Ищет устройства с заданным именем во всех комнатах дома.
+    ///
+    /// Имя устройства уникально только в рамках комнаты, поэтому
+    /// устройства с одинаковыми именами могут находиться в разных
+    /// комнатах. Метод возвращает все найденные совпадения.
+    ///
+    /// # Параметры
+    ///
+    /// - `name`: `&str` — Имя искомого устройства.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает вектор пар (комната, устройство) в порядке добавления
+    /// комнат. Если устройство не найдено, вектор будет пустым.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartThermometer;
+    ///
+    /// let mut kitchen = SmartRoom::new(String::from("Kitchen"));
+    /// kitchen.plug(Arc::new(SmartThermometer::new(String::from("Sensor")))).unwrap();
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(kitchen).unwrap();
+    ///
+    /// let found = smart_house.find_all_devices("Sensor");
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].0.name(), "Kitchen");
+    /// ```
+    pub fn find_all_devices(&self, name: &str) -> Vec<(&SmartRoom, Arc<dyn Pluggable>)> {
+        self.rooms_with_device(name)
+            .into_iter()
+            .flat_map(|index| self.device_in_room(index, name))
+            .collect()
+    }
+
+    /// Ищет первую комнату, к которой подключено устройство с заданным
+    /// именем.
+    ///
+    /// В отличие от `find_all_devices`, возвращает только первое
+    /// совпадение в порядке комнат. Поиск выполняется по индексу
+    /// устройств, который обновляется при изменении комнат, поэтому не
+    /// требует перебора всех комнат.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает `Some((комната, устройство))` или `None`, если устройство
+    /// не подключено ни к одной комнате.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.plug("Kitchen", Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///
+    /// let (room, _) = smart_house.find_device("Kettle").unwrap();
+    /// assert_eq!(room.name(), "Kitchen");
+    /// assert!(smart_house.find_device("Toaster").is_none());
+    /// ```
+    pub fn find_device(&self, name: &str) -> Option<(&SmartRoom, Arc<dyn Pluggable>)> {
+        let index = *self.rooms_with_device(name).first()?;
+
+        self.device_in_room(index, name).into_iter().next()
+    }
+
+    /// Возвращает позиции комнат с устройством `name`, перестраивая индекс
+    /// устройств, если он устарел после `iter_rooms_mut`.
+    fn rooms_with_device(&self, name: &str) -> Vec<usize> {
+        let mut index = self.device_index.borrow_mut();
+
+        if index.stale {
+            index.rebuild(&self.rooms);
+        }
+
+        index.rooms.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Возвращает комнаты с устройством `name` среди комнаты с позицией
+    /// `index` и ее вложенных комнат вместе с самим устройством.
+    fn device_in_room(&self, index: usize, name: &str) -> Vec<(&SmartRoom, Arc<dyn Pluggable>)> {
+        self.rooms
+            .get(index)
+            .map(|room| room.walk())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(_, room)| {
+                room.devices
+                    .iter()
+                    .find(|d| d.name() == name)
+                    .map(|d| (room, Arc::clone(d)))
+            })
+            .collect()
+    }
+
+    /// Возвращает комнаты, упорядоченные по убыванию потребляемой мощности.
+    ///
+    /// Комнаты с одинаковым потреблением упорядочиваются по имени, чтобы
+    /// результат не зависел от порядка добавления.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает вектор ссылок на комнаты дома.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+    ///
+    /// let names: Vec<&str> = smart_house.rooms_by_power().iter().map(|r| r.name()).collect();
+    /// assert_eq!(names, vec!["Bedroom", "Kitchen"]);
+    /// ```
+    pub fn rooms_by_power(&self) -> Vec<&SmartRoom> {
+        let mut rooms: Vec<&SmartRoom> = self.rooms.iter().collect();
+
+        rooms.sort_by(|a, b| {
+            b.power_usage()
+                .total_cmp(&a.power_usage())
+                .then_with(|| a.name().cmp(b.name()))
+        });
+
+        rooms
+    }
+
+    /// Выгружает план дома в виде строк `KEY=VALUE` для систем управления
+    /// конфигурацией.
+    ///
+    /// Комнаты и устройства нумеруются с единицы в порядке добавления:
+    /// `HOUSE_NAME`, `ROOM_1`, `ROOM_1_DEVICE_1` и т.д. Вложенные комнаты
+    /// выгружаются с ключами вида `ROOM_1_SUBROOM_1` и
+    /// `ROOM_1_SUBROOM_1_DEVICE_1`. Символы имен, кроме
+    /// латинских букв, цифр, `_`, `-` и `.`, заменяются на `_`, чтобы значения
+    /// не требовали экранирования.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// assert_eq!(smart_house.export_env(), "HOUSE_NAME=My_Smart_Home\nROOM_1=Kitchen\n");
+    /// ```
+    pub fn export_env(&self) -> String {
+        fn sanitize(value: &str) -> String {
+            value
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' => c,
+                    _ => '_',
+                })
+                .collect()
+        }
+
+        fn export_room(out: &mut String, key: &str, room: &SmartRoom) {
+            out.push_str(&format!("{}={}\n", key, sanitize(room.name())));
+
+            for (j, device) in room.devices.iter().enumerate() {
+                out.push_str(&format!(
+                    "{}_DEVICE_{}={}\n",
+                    key,
+                    j + 1,
+                    sanitize(device.name())
+                ));
+            }
+
+            for (k, subroom) in room.subrooms.iter().enumerate() {
+                export_room(out, &format!("{}_SUBROOM_{}", key, k + 1), subroom);
+            }
+        }
+
+        let mut out = format!("HOUSE_NAME={}\n", sanitize(&self.name));
+
+        for (i, room) in self.rooms.iter().enumerate() {
+            export_room(&mut out, &format!("ROOM_{}", i + 1), room);
+        }
+
+        out
+    }
+
+    /// Создает неизменяемый снимок дома.
+    ///
+    /// Последующие изменения дома на снимок не влияют.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// let snapshot = smart_house.snapshot();
+    /// assert_eq!(snapshot.name, "My Smart Home");
+    /// assert_eq!(snapshot.rooms[0].name, "Kitchen");
+    /// ```
+    pub fn snapshot(&self) -> HouseSnapshot {
+        HouseSnapshot {
+            name: self.name.clone(),
+            rooms: self.rooms.iter().map(|room| room.snapshot()).collect(),
+        }
+    }
+
+    /// Строит граф комнат дома, в котором соседними считаются комнаты
+    /// с общими устройствами.
+    ///
+    /// Вершинами графа являются комнаты дома; устройства вложенных комнат
+    /// относятся к вершине комнаты дома, в которую они вложены.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Office"))).unwrap();
+    /// smart_house.add(SmartRoom::new(String::from("Hall"))).unwrap();
+    /// smart_house
+    ///     .plug_shared(&["Office", "Hall"], Arc::new(SmartSocket::new(String::from("Printer"))))
+    ///     .unwrap();
+    ///
+    /// let graph = smart_house.as_graph();
+    /// assert_eq!(graph.neighbors("Office"), vec!["Hall"]);
+    /// ```
+    pub fn as_graph(&self) -> HouseGraph {
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); self.rooms.len()];
+
+        let devices: Vec<Vec<Arc<dyn Pluggable>>> = self
+            .rooms
+            .iter()
+            .map(|room| room.all_devices_arc())
+            .collect();
+
+        for (a, own) in devices.iter().enumerate() {
+            for (b, other) in devices.iter().enumerate() {
+                let shared = own
+                    .iter()
+                    .any(|device| other.iter().any(|d| Arc::ptr_eq(d, device)));
+
+                if a != b && shared {
+                    neighbors[a].push(b);
+                }
+            }
+        }
+
+        HouseGraph {
+            rooms: self.snapshot().rooms,
+            neighbors,
+        }
+    }
+
+    /// Группирует комнаты дома по категориям.
+    ///
+    /// В каждой группе комнаты следуют в порядке добавления в дом.
+    /// Категории без комнат в результат не попадают.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{RoomCategory, SmartHouse, SmartRoom};
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house
+    ///     .add(SmartRoom::with_category(String::from("Kitchen"), RoomCategory::Kitchen))
+    ///     .unwrap();
+    ///
+    /// let groups = smart_house.rooms_by_category();
+    /// assert_eq!(groups[&RoomCategory::Kitchen].len(), 1);
+    /// assert!(!groups.contains_key(&RoomCategory::Bedroom));
+    /// ```
+    pub fn rooms_by_category(&self) -> HashMap<RoomCategory, Vec<&SmartRoom>> {
+        let mut groups: HashMap<RoomCategory, Vec<&SmartRoom>> = HashMap::new();
+
+        for room in self.rooms.iter() {
+            groups.entry(room.category()).or_default().push(room);
+        }
+
+        groups
+    }
+
+    /// Создает отчет на основе заданного типа отчета.
+    ///
+    /// Этот метод принимает объект, реализующий трейт `Reportable`, и вызывает
+    /// метод `make`, чтобы сгенерировать отчет, используя данные из текущего
+    /// экземпляра `SmartHouse`. Это позволяет разделить логику создания отчетов
+    /// на разные структуры, реализующие данный трейт.
+    ///
+    /// # Параметры
+    ///
+    /// - `report`: `T` — Объект, реализующий трейт `Reportable`. Этот объект
+    ///   содержит логику для генерации отчета на основе данных умного дома.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(String)` — Если отчет успешно создан. Возвращает строку,
+    ///   представляющую сгенерированный отчет.
+    /// - `Err(Box<dyn Error>)` — Если произошла ошибка в процессе создания отчета.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::error::Error;
+    /// use lesson_4::smart::report::Reportable;
+    /// use lesson_4::smart::location::SmartHouse;
+    ///
+    /// struct SimpleReport;
+    ///
+    /// impl Reportable for SimpleReport {
+    ///     fn make(&self, smart_house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+    ///         Ok(format!("Report for: {}", smart_house.name()))
+    ///     }
+    /// }
+    ///
+    /// let smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// let report = SimpleReport;
+    ///
+    /// match smart_house.create_report(report) {
+    ///     Ok(report_text) => println!("{}", report_text),
+    ///     Err(e) => println!("Error creating report: {}", e),
+    /// }
+    /// ```
+    pub fn create_report<T: Reportable>(&self, report: T) -> Result<String, Box<dyn Error>> {
+        report.make(self)
+    }
+
+    /// Записывает отчет заданного формата в `w` по мере построения.
+    ///
+    /// Отчет выводится по комнатам и не собирается целиком в памяти, что
+    /// важно для домов с большим числом устройств.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+    /// use lesson_4::smart::report::ReportFormat;
+    ///
+    /// let mut smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// smart_house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+    ///
+    /// let mut out: Vec<u8> = Vec::new();
+    /// smart_house.stream_report(&mut out, ReportFormat::Csv).unwrap();
+    /// assert_eq!(out, b"room,device\n");
+    /// ```
+    pub fn stream_report(&self, w: &mut dyn Write, format: ReportFormat) -> io::Result<()> {
+        make_report(format).write_to(self, w)
+    }
+
+    /// Создает отчет, не забирая владение генератором отчета.
+    ///
+    /// В отличие от `create_report`, принимает ссылку на типаж-объект, поэтому
+    /// один настроенный экземпляр отчета можно использовать повторно, а отчеты
+    /// можно хранить в `Vec<Box<dyn Reportable>>`.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::SmartHouse;
+    /// use lesson_4::smart::report::{Reportable, TreeReport};
+    ///
+    /// let smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// let reports: Vec<Box<dyn Reportable>> = vec![Box::new(TreeReport)];
+    ///
+    /// for report in reports.iter() {
+    ///     assert!(smart_house.create_report_with(report.as_ref()).is_ok());
+    /// }
+    /// ```
+    pub fn create_report_with(&self, report: &dyn Reportable) -> Result<String, Box<dyn Error>> {
+        report.make(self)
+    }
+}
+
+/// Неизменяемый снимок состояния дома.
+///
+/// Содержит только имена дома, комнат и устройств и не хранит ссылок на
+/// устройства, поэтому его можно свободно копировать и передавать между
+/// потоками, например, слою отображения.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HouseSnapshot {
+    pub name: String,
+    pub rooms: Vec<RoomSnapshot>,
+}
+
+/// Снимок комнаты в составе `HouseSnapshot`.
+///
+/// `devices` содержит только устройства самой комнаты, устройства
+/// вложенных комнат находятся в их снимках в `subrooms`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomSnapshot {
+    pub name: String,
+    pub devices: Vec<String>,
+    pub subrooms: Vec<RoomSnapshot>,
+}
+
+/// Запись плоской описи устройств дома, см. `SmartHouse::inventory`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInventoryEntry {
+    pub room: String,
+    pub name: String,
+    pub kind: &'static str,
+    pub power: f64,
+}
+
+/// Представление дома в виде графа комнат.
+///
+/// Комнаты являются вершинами графа; две комнаты соседствуют, если к ним
+/// обеим подключено одно и то же устройство (через `plug_shared`).
+/// Граф хранит только имена и не ссылается на устройства.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HouseGraph {
+    rooms: Vec<RoomSnapshot>,
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl HouseGraph {
+    /// Возвращает имена комнат в порядке добавления в дом.
+    pub fn rooms(&self) -> Vec<&str> {
+        self.rooms.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    /// Возвращает имена устройств комнаты или `None`, если комнаты нет.
+    pub fn devices_in(&self, room: &str) -> Option<&[String]> {
+        self.position(room)
+            .map(|i| self.rooms[i].devices.as_slice())
+    }
+
+    /// Возвращает имена комнат, имеющих с `room` общие устройства.
+    pub fn neighbors(&self, room: &str) -> Vec<&str> {
+        match self.position(room) {
+            Some(i) => self.neighbors[i]
+                .iter()
+                .map(|&j| self.rooms[j].name.as_str())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn position(&self, room: &str) -> Option<usize> {
+        self.rooms.iter().position(|r| r.name == room)
+    }
+}
+
+/// Построение дома из названия и списка комнат.
+///
+/// Комнаты добавляются по порядку методом `SmartHouse::add`, поэтому
+/// повторяющиеся или пустые имена комнат приводят к ошибке.
+///
+/// # Пример
+///
+/// ```rust
+/// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+///
+/// let rooms = vec![
+///     SmartRoom::new(String::from("Kitchen")),
+///     SmartRoom::new(String::from("Kitchen")),
+/// ];
+///
+/// assert!(SmartHouse::try_from((String::from("My Smart Home"), rooms)).is_err());
+/// ```
+impl TryFrom<(String, Vec<SmartRoom>)> for SmartHouse {
+    type Error = SmartHouseError;
+
+    fn try_from((name, rooms): (String, Vec<SmartRoom>)) -> Result<Self, Self::Error> {
+        let mut house = SmartHouse::new(name);
+
+        for room in rooms {
+            house.add(room)?;
+        }
+
+        Ok(house)
+    }
+}
+
+/// Изменяемая ссылка на комнату дома, выдаваемая `SmartHouse::get_room_mut`.
+///
+/// Предоставляет доступ к комнате через `Deref`/`DerefMut`. Если комната
+/// была взята на изменение, при уничтожении увеличивает версию дома и
+/// обновляет записи комнаты в индексе устройств.
+pub struct SmartRoomMut<'a> {
+    room: &'a mut SmartRoom,
+    version: &'a mut u64,
+    device_index: &'a mut DeviceIndex,
+    position: usize,
+    changed: bool,
+}
+
+impl Deref for SmartRoomMut<'_> {
+    type Target = SmartRoom;
+
+    fn deref(&self) -> &SmartRoom {
+        self.room
+    }
+}
+
+impl DerefMut for SmartRoomMut<'_> {
+    fn deref_mut(&mut self) -> &mut SmartRoom {
+        self.changed = true;
+        self.room
+    }
+}
+
+impl Drop for SmartRoomMut<'_> {
+    fn drop(&mut self) {
+        if self.changed {
+            *self.version += 1;
+            self.device_index.update_room(self.position, self.room);
+        }
+    }
+}
+
+impl PartialEq for SmartRoom {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl fmt::Display for SmartHouse {
+    /// Форматирует представление умного дома для вывода в строку.
+    ///
+    /// Этот метод реализует трейты `fmt::Display`, позволяя
+    /// выводить экземпляр `SmartHouse` в удобочитаемом формате.
+    /// Метод записывает имя умного дома в строковом формате,
+    /// предшествуемое стрелкой (`->`).
+    ///
+    /// # Параметры
+    ///
+    /// - `f`: `&mut std::fmt::Formatter` — Мутируемая ссылка на форматтер,
+    ///   который используется для записи форматированной строки.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `std::fmt::Result` — Результат выполнения форматирования.
+    ///   Если форматирование прошло успешно, возвращается `Ok(())`,
+    ///   в противном случае возвращается ошибка формата.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use lesson_4::smart::location::SmartHouse;
+    /// let smart_house = SmartHouse::new(String::from("My Smart Home"));
+    /// println!("{}", smart_house);
+    /// ```
+    ///
+    /// Выход:
+    /// ```plaintext
+    /// -> House: My Smart Home
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "-> House: {}", self.name())
     }
 }
 
+/// Ошибки, возникающие при работе с умной комнатой `SmartRoom`.
+///
+/// # Варианты
+///
+/// - `DeviceAlreadyPlugged(String)` — устройство с указанным именем
+///   уже подключено к комнате.
+/// - `InvalidName(String)` — имя устройства пустое или состоит только
+///   из пробельных символов.
+/// - `DeviceNotFound(String)` — устройство с указанным именем не
+///   подключено к комнате.
+/// - `SubroomAlreadyExists(String)` — вложенная комната с указанным
+///   именем уже добавлена в комнату.
+/// - `InvalidSubroomName(String)` — имя вложенной комнаты пустое или
+///   состоит только из пробельных символов.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmartRoomError {
+    DeviceAlreadyPlugged(String),
+    InvalidName(String),
+    DeviceNotFound(String),
+    SubroomAlreadyExists(String),
+    InvalidSubroomName(String),
+}
+
+impl fmt::Display for SmartRoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeviceAlreadyPlugged(name) => {
+                write!(f, "Device with name {} already pluged", name)
+            }
+            Self::InvalidName(name) => write!(f, "invalid device name {:?}", name),
+            Self::DeviceNotFound(name) => write!(f, "device {} not found", name),
+            Self::SubroomAlreadyExists(name) => write!(f, "subroom {} already added", name),
+            Self::InvalidSubroomName(name) => write!(f, "invalid subroom name {:?}", name),
+        }
+    }
+}
+
+impl Error for SmartRoomError {}
+
+/// Категория (назначение) комнаты.
+///
+/// Используется для группировки комнат в отчетах. Комната без явно
+/// заданной категории относится к `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RoomCategory {
+    Bedroom,
+    Kitchen,
+    Bathroom,
+    #[default]
+    Other,
+}
+
 /// Структура `SmartRoom` представляет собой умную комнату,
 /// которая содержит название и коллекцию устройств.
 ///
@@ -286,12 +2017,40 @@ impl fmt::Display for SmartHouse {
 /// - `devices`: вектор, содержащий устройства, которые могут быть подключены
 ///   к этой комнате. Устройства представлены через указатели `Arc` на динамические
 ///   трейт-объекты `Pluggable`.
+/// - `subscribers`: подписчики дома, которому принадлежит комната. Задается
+///   домом при добавлении комнаты; копия комнаты подписчиков не наследует.
+/// - `category`: категория комнаты, по умолчанию `RoomCategory::Other`.
+/// - `power_alert`: порог мощности и обработчик, вызываемый при его
+///   превышении после подключения устройства. Копия комнаты обработчик не
+///   наследует.
+/// - `normalized`: сравнивать ли имена устройств после нормализации
+///   пробелов (см. `SmartRoom::new_normalized`).
+/// - `subrooms`: вложенные комнаты (например, гардероб внутри спальни).
+///   Их устройства учитываются в мощности родительской комнаты.
 ///
 /// ```
-#[derive(Clone)]
 pub struct SmartRoom {
     name: String,
     devices: Vec<Arc<dyn Pluggable>>,
+    subrooms: Vec<SmartRoom>,
+    category: RoomCategory,
+    subscribers: Option<Subscribers>,
+    power_alert: Option<(f64, Box<PowerAlertCallback>)>,
+    normalized: bool,
+}
+
+impl Clone for SmartRoom {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            devices: self.devices.clone(),
+            subrooms: self.subrooms.clone(),
+            category: self.category,
+            subscribers: None,
+            power_alert: None,
+            normalized: self.normalized,
+        }
+    }
 }
 
 impl SmartRoom {
@@ -321,13 +2080,124 @@ impl SmartRoom {
         Self {
             name,
             devices: Vec::default(),
+            subrooms: Vec::default(),
+            category: RoomCategory::default(),
+            subscribers: None,
+            power_alert: None,
+            normalized: false,
+        }
+    }
+
+    /// Создает пустую комнату с нормализацией имен.
+    ///
+    /// У имени комнаты и имен подключаемых устройств убираются пробелы по
+    /// краям, а серии пробелов внутри заменяются одним пробелом. Проверка
+    /// уникальности в `plug`, поиск заменяемого устройства в
+    /// `plug_or_replace`, а также `unplug`, `contains` и `is_connected`
+    /// сравнивают уже нормализованные имена, поэтому `" TV "` и `"TV"`
+    /// считаются одним и тем же устройством. Сами устройства не
+    /// переименовываются.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new_normalized(String::from("  Living   room "));
+    /// assert_eq!(room.name(), "Living room");
+    ///
+    /// room.plug(Arc::new(SmartSocket::new(String::from("TV")))).unwrap();
+    /// assert!(room.plug(Arc::new(SmartSocket::new(String::from(" TV ")))).is_err());
+    /// ```
+    pub fn new_normalized(name: String) -> Self {
+        Self {
+            normalized: true,
+            ..Self::new(normalize_name(&name))
+        }
+    }
+
+    /// Сравнивает имена устройств с учетом режима нормализации комнаты.
+    fn same_name(&self, a: &str, b: &str) -> bool {
+        match self.normalized {
+            true => normalize_name(a) == normalize_name(b),
+            false => a == b,
+        }
+    }
+
+    /// Создает пустую комнату заданной категории.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use lesson_4::smart::location::{RoomCategory, SmartRoom};
+    /// let room = SmartRoom::with_category(String::from("Bedroom"), RoomCategory::Bedroom);
+    /// assert_eq!(room.category(), RoomCategory::Bedroom);
+    /// ```
+    pub fn with_category(name: String, category: RoomCategory) -> Self {
+        Self {
+            category,
+            ..Self::new(name)
+        }
+    }
+
+    /// Возвращает категорию комнаты.
+    pub fn category(&self) -> RoomCategory {
+        self.category
+    }
+
+    /// Создает комнату и подключает к ней все переданные устройства.
+    ///
+    /// Устройства подключаются по порядку методом `plug`. Построение
+    /// прерывается на первом устройстве, имя которого совпадает с уже
+    /// подключенным.
+    ///
+    /// # Аргументы
+    ///
+    /// - `name`: Строка, представляющая название новой комнаты.
+    /// - `devices`: Коллекция устройств, которые необходимо подключить.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// - `Ok(SmartRoom)` — комната со всеми подключенными устройствами.
+    /// - `Err(SmartRoomError::DeviceAlreadyPlugged)` — если среди устройств
+    ///   встретились повторяющиеся имена.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::Pluggable;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let devices: Vec<Arc<dyn Pluggable>> = vec![
+    ///     Arc::new(SmartSocket::new(String::from("TV"))),
+    ///     Arc::new(SmartSocket::new(String::from("Lamp"))),
+    /// ];
+    ///
+    /// let room = SmartRoom::try_from_devices(String::from("Hall"), devices).unwrap();
+    /// assert_eq!(room.devices(), vec!["TV".to_string(), "Lamp".to_string()]);
+    /// ```
+    pub fn try_from_devices(
+        name: String,
+        devices: impl IntoIterator<Item = Arc<dyn Pluggable>>,
+    ) -> Result<Self, SmartRoomError> {
+        let mut room = Self::new(name);
+
+        for device in devices {
+            room.plug(device)?;
         }
+
+        Ok(room)
     }
+
     /// Подключает устройство к комнате.
     ///
     /// Этот метод пытается добавить устройство в список устройств,
-    /// подключенных к `SmartRoom`. Если устройство с таким же именем
-    /// уже подключено, метод вернет ошибку.
+    /// подключенных к `SmartRoom`. Если имя устройства пустое или
+    /// устройство с таким же именем уже подключено, метод вернет ошибку.
     ///
     /// # Аргументы
     ///
@@ -336,10 +2206,12 @@ impl SmartRoom {
     ///
     /// # Возвращаемое значение
     ///
-    /// Возвращает `Result<(), Box<dyn Error>>`, где:
+    /// Возвращает `Result<(), SmartRoomError>`, где:
     /// - `Ok(())` означает успешное подключение устройства.
-    /// - `Err(err)` содержит информацию об ошибке, если устройство
+    /// - `Err(SmartRoomError::DeviceAlreadyPlugged)`, если устройство
     ///   с заданным именем уже подключено.
+    /// - `Err(SmartRoomError::InvalidName)`, если имя устройства пустое
+    ///   или состоит только из пробельных символов.
     ///
     /// # Пример
     ///
@@ -356,20 +2228,187 @@ impl SmartRoom {
     /// // Пытаемся подключить то же устройство снова
     /// assert!(room.plug(device).is_err());
     /// ```
-    pub fn plug(&mut self, device: Arc<dyn Pluggable>) -> Result<(), Box<dyn Error>> {
-        match &self.devices.iter().find(|&d| d.name() == device.name()) {
-            Some(_) => Err(format!("Device with name {} already pluged", device.name()).into()),
+    pub fn plug(&mut self, device: Arc<dyn Pluggable>) -> Result<(), SmartRoomError> {
+        if !is_valid_name(device.name()) {
+            return Err(SmartRoomError::InvalidName(device.name().to_string()));
+        }
+
+        match &self
+            .devices
+            .iter()
+            .find(|&d| self.same_name(d.name(), device.name()))
+        {
+            Some(_) => Err(SmartRoomError::DeviceAlreadyPlugged(
+                device.name().to_string(),
+            )),
             None => {
                 self.devices.push(device);
+                self.notify(
+                    self.devices[self.devices.len() - 1].name(),
+                    ChangeKind::Plugged,
+                );
+                self.check_power_alert();
                 Ok(())
             }
         }
     }
 
-    #[allow(dead_code)]
-    pub fn unplug(&mut self, device: &str) {
-        if let Some(index) = self.devices.iter().position(|d| d.name() == device) {
-            self.devices.remove(index);
+    /// Подключает устройство, вставляя его в заданную позицию.
+    ///
+    /// Проверки те же, что и в `plug`. Позиция ограничивается количеством
+    /// подключенных устройств, поэтому слишком большой индекс означает
+    /// добавление в конец.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap();
+    /// room.plug_at(0, Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///
+    /// assert_eq!(room.devices(), vec!["Kettle", "Toaster"]);
+    /// ```
+    pub fn plug_at(
+        &mut self,
+        index: usize,
+        device: Arc<dyn Pluggable>,
+    ) -> Result<(), SmartRoomError> {
+        self.plug(device)?;
+
+        let last = self.devices.len() - 1;
+        let device = self.devices.remove(last);
+        self.devices.insert(index.min(last), device);
+
+        Ok(())
+    }
+
+    /// Подключает устройство, заменяя подключенное устройство с тем же
+    /// именем.
+    ///
+    /// Новое устройство занимает место заменяемого. Подписчики дома получают
+    /// уведомление об отключении старого устройства и подключении нового.
+    /// После подключения проверяется порог мощности, заданный
+    /// `set_power_alert`.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(Some(old))` — Замененное устройство.
+    /// - `Ok(None)` — Если устройства с таким именем не было и новое
+    ///   устройство добавлено в конец списка.
+    /// - `Err(SmartRoomError::InvalidName)` — Если имя устройства пустое
+    ///   или состоит только из пробельных символов. Комната не меняется.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// assert!(room.plug_or_replace(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap().is_none());
+    /// assert!(room.plug_or_replace(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap().is_some());
+    /// assert!(room.plug_or_replace(Arc::new(SmartSocket::new(String::from(" ")))).is_err());
+    /// assert_eq!(room.devices().len(), 1);
+    /// ```
+    pub fn plug_or_replace(
+        &mut self,
+        device: Arc<dyn Pluggable>,
+    ) -> Result<Option<Arc<dyn Pluggable>>, SmartRoomError> {
+        if !is_valid_name(device.name()) {
+            return Err(SmartRoomError::InvalidName(device.name().to_string()));
+        }
+
+        let old = match self
+            .devices
+            .iter()
+            .position(|d| self.same_name(d.name(), device.name()))
+        {
+            Some(index) => {
+                let old = std::mem::replace(&mut self.devices[index], device);
+                self.notify(old.name(), ChangeKind::Unplugged);
+                self.notify(self.devices[index].name(), ChangeKind::Plugged);
+                Some(old)
+            }
+            None => {
+                self.devices.push(device);
+                self.notify(
+                    self.devices[self.devices.len() - 1].name(),
+                    ChangeKind::Plugged,
+                );
+                None
+            }
+        };
+        self.check_power_alert();
+
+        Ok(old)
+    }
+
+    #[allow(dead_code)]
+    pub fn unplug(&mut self, device: &str) {
+        if let Some(index) = self
+            .devices
+            .iter()
+            .position(|d| self.same_name(d.name(), device))
+        {
+            let removed = self.devices.remove(index);
+            self.notify(removed.name(), ChangeKind::Unplugged);
+        }
+    }
+
+    /// Устанавливает обработчик превышения мощности комнаты.
+    ///
+    /// После каждого подключения устройства методом `plug` обработчик
+    /// вызывается с новой суммарной мощностью комнаты, если она превышает
+    /// `threshold`. Повторный вызов заменяет ранее установленный обработчик.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let fired = Rc::new(Cell::new(false));
+    /// let flag = Rc::clone(&fired);
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.set_power_alert(1000.0, Box::new(move |_| flag.set(true)));
+    ///
+    /// let kettle = SmartSocket::with_power(String::from("Kettle"), 2000.0);
+    /// kettle.turn_on();
+    /// room.plug(Arc::new(kettle)).unwrap();
+    ///
+    /// assert!(fired.get());
+    /// ```
+    pub fn set_power_alert(&mut self, threshold: f64, cb: Box<dyn Fn(f64)>) {
+        self.power_alert = Some((threshold, cb));
+    }
+
+    /// Вызывает обработчик превышения мощности, если порог превышен.
+    fn check_power_alert(&self) {
+        if let Some((threshold, cb)) = &self.power_alert {
+            let total = self.power_usage();
+
+            if total > *threshold {
+                cb(total);
+            }
+        }
+    }
+
+    /// Сообщает подписчикам дома об изменении состава устройств.
+    fn notify(&self, device: &str, kind: ChangeKind) {
+        if let Some(subscribers) = &self.subscribers {
+            for cb in subscribers.borrow().iter() {
+                cb(self.name(), device, kind);
+            }
         }
     }
 
@@ -406,7 +2445,66 @@ impl SmartRoom {
     /// assert!(!room.is_connected(&*device2)); // Проверка, что устройство не подключено
     /// ```
     pub fn is_connected(&self, device: &dyn Pluggable) -> bool {
-        self.devices.iter().any(|d| d.name() == device.name())
+        self.contains(device.name())
+    }
+
+    /// Проверяет, подключено ли к комнате устройство с заданным именем.
+    ///
+    /// В отличие от `is_connected`, не требует создавать устройство: достаточно
+    /// знать его имя.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap();
+    ///
+    /// assert!(room.contains("Toaster"));
+    /// assert!(!room.contains("Mixer"));
+    /// ```
+    pub fn contains(&self, device_name: &str) -> bool {
+        self.devices
+            .iter()
+            .any(|d| self.same_name(d.name(), device_name))
+    }
+
+    /// Ищет устройства, имя которых содержит заданную подстроку.
+    ///
+    /// Сравнение выполняется без учета регистра.
+    ///
+    /// # Аргументы
+    ///
+    /// - `query`: Подстрока, которую нужно найти в имени устройства.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает вектор найденных устройств в порядке подключения.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Living Room"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Table Lamp")))).unwrap();
+    /// room.plug(Arc::new(SmartSocket::new(String::from("TV")))).unwrap();
+    ///
+    /// assert_eq!(room.search_devices("LAMP").len(), 1);
+    /// ```
+    pub fn search_devices(&self, query: &str) -> Vec<Arc<dyn Pluggable>> {
+        let query = query.to_lowercase();
+
+        self.devices
+            .iter()
+            .filter(|d| d.name().to_lowercase().contains(&query))
+            .cloned()
+            .collect()
     }
 
     /// Возвращает список имен подключенных устройств.
@@ -445,6 +2543,462 @@ impl SmartRoom {
         self.devices.iter().map(|d| d.name().to_string()).collect()
     }
 
+    /// Возвращает срез подключенных устройств в порядке подключения.
+    ///
+    /// В отличие от `devices`, не копирует имена и дает доступ к самим
+    /// устройствам только для чтения.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Living Room"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("TV")))).unwrap();
+    ///
+    /// assert_eq!(room.devices_arc()[0].name(), "TV");
+    /// ```
+    pub fn devices_arc(&self) -> &[Arc<dyn Pluggable>] {
+        &self.devices
+    }
+
+    /// Возвращает имена подключенных устройств в алфавитном порядке.
+    ///
+    /// В отличие от `devices`, результат не зависит от порядка подключения,
+    /// что делает отчеты воспроизводимыми.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Living Room"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("TV")))).unwrap();
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Lamp")))).unwrap();
+    ///
+    /// assert_eq!(room.device_names_sorted(), vec!["Lamp", "TV"]);
+    /// ```
+    pub fn device_names_sorted(&self) -> Vec<String> {
+        let mut names = self.devices();
+        names.sort();
+        names
+    }
+
+    /// Возвращает состояние каждого подключенного устройства.
+    ///
+    /// В отличие от `devices`, который возвращает только имена, этот метод
+    /// собирает строки `status()` всех устройств в порядке подключения.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.plug(Arc::new(SmartSocket::with_power(String::from("Kettle"), 2000.0))).unwrap();
+    ///
+    /// assert_eq!(room.devices_detailed(), vec!["Socket[Kettle]: off, 2000W".to_string()]);
+    /// ```
+    pub fn devices_detailed(&self) -> Vec<String> {
+        self.devices.iter().map(|d| d.status()).collect()
+    }
+
+    /// Создает копию комнаты с независимыми копиями устройств.
+    ///
+    /// В отличие от `clone`, устройства копии не разделяют состояние с
+    /// устройствами исходной комнаты: включение розетки в копии не влияет на
+    /// оригинал. Устройства, не поддерживающие копирование (`clone_box`
+    /// возвращает `None`), остаются общими с исходной комнатой.
+    ///
+    /// # Пример
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap();
+    ///
+    /// let copy = room.deep_clone();
+    /// assert!(copy.deep_eq(&room));
+    /// ```
+    pub fn deep_clone(&self) -> SmartRoom {
+        Self {
+            name: self.name.clone(),
+            devices: self
+                .devices
+                .iter()
+                .map(|d| {
+                    d.clone_box()
+                        .map(Arc::from)
+                        .unwrap_or_else(|| Arc::clone(d))
+                })
+                .collect(),
+            subrooms: self.subrooms.iter().map(|r| r.deep_clone()).collect(),
+            category: self.category,
+            subscribers: None,
+            power_alert: None,
+            normalized: self.normalized,
+        }
+    }
+
+    /// Добавляет вложенную комнату.
+    ///
+    /// Имена вложенных комнат проверяются так же, как имена комнат в
+    /// `SmartHouse::add`: пустые и повторяющиеся среди вложенных комнат
+    /// этой комнаты имена отклоняются.
+    ///
+    /// Устройства вложенной комнаты не участвуют в проверке уникальности
+    /// имен при подключении к родительской комнате, но учитываются в
+    /// `power_usage`, `total_of` и `all_devices`.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Возвращает:
+    /// - `Ok(())` — Если комната добавлена.
+    /// - `Err(SmartRoomError::InvalidSubroomName)` — Если имя комнаты
+    ///   пустое или состоит только из пробельных символов.
+    /// - `Err(SmartRoomError::SubroomAlreadyExists)` — Если вложенная
+    ///   комната с таким именем уже есть.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut closet = SmartRoom::new(String::from("Closet"));
+    /// closet.plug(Arc::new(SmartSocket::new(String::from("Light")))).unwrap();
+    ///
+    /// let mut bedroom = SmartRoom::new(String::from("Bedroom"));
+    /// bedroom.add_subroom(closet).unwrap();
+    ///
+    /// assert_eq!(bedroom.subrooms()[0].name(), "Closet");
+    /// assert!(bedroom.devices().is_empty());
+    /// assert_eq!(bedroom.all_devices(), vec!["Light"]);
+    /// assert!(bedroom.add_subroom(SmartRoom::new(String::from("Closet"))).is_err());
+    /// ```
+    pub fn add_subroom(&mut self, room: SmartRoom) -> Result<(), SmartRoomError> {
+        if !is_valid_name(room.name()) {
+            return Err(SmartRoomError::InvalidSubroomName(room.name().to_string()));
+        }
+
+        if self.subrooms.iter().any(|r| r.name() == room.name()) {
+            return Err(SmartRoomError::SubroomAlreadyExists(
+                room.name().to_string(),
+            ));
+        }
+
+        self.subrooms.push(room);
+
+        Ok(())
+    }
+
+    /// Возвращает вложенные комнаты в порядке добавления.
+    pub fn subrooms(&self) -> &[SmartRoom] {
+        &self.subrooms
+    }
+
+    /// Возвращает имена устройств комнаты и всех вложенных комнат.
+    ///
+    /// Сначала перечисляются устройства самой комнаты, затем устройства
+    /// вложенных комнат в порядке их добавления.
+    pub fn all_devices(&self) -> Vec<String> {
+        let mut names = self.devices();
+
+        for room in self.subrooms.iter() {
+            names.extend(room.all_devices());
+        }
+
+        names
+    }
+
+    /// Возвращает устройства комнаты и всех вложенных комнат в том же
+    /// порядке, что и `all_devices`.
+    pub fn all_devices_arc(&self) -> Vec<Arc<dyn Pluggable>> {
+        self.walk()
+            .into_iter()
+            .flat_map(|(_, room)| room.devices.iter().cloned())
+            .collect()
+    }
+
+    /// Возвращает саму комнату и все вложенные комнаты в порядке обхода в
+    /// глубину вместе с уровнем вложенности (у самой комнаты — 0).
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use lesson_4::smart::location::SmartRoom;
+    ///
+    /// let mut closet = SmartRoom::new(String::from("Closet"));
+    /// closet.add_subroom(SmartRoom::new(String::from("Shelf"))).unwrap();
+    /// let mut bedroom = SmartRoom::new(String::from("Bedroom"));
+    /// bedroom.add_subroom(closet).unwrap();
+    ///
+    /// let rooms: Vec<(usize, &str)> = bedroom
+    ///     .walk()
+    ///     .into_iter()
+    ///     .map(|(depth, room)| (depth, room.name()))
+    ///     .collect();
+    /// assert_eq!(rooms, vec![(0, "Bedroom"), (1, "Closet"), (2, "Shelf")]);
+    /// ```
+    pub fn walk(&self) -> Vec<(usize, &SmartRoom)> {
+        let mut out = Vec::new();
+        let mut pending = vec![(0, self)];
+
+        while let Some((depth, room)) = pending.pop() {
+            out.push((depth, room));
+            pending.extend(room.subrooms.iter().rev().map(|r| (depth + 1, r)));
+        }
+
+        out
+    }
+
+    /// Возвращает снимок комнаты вместе со снимками вложенных комнат.
+    pub fn snapshot(&self) -> RoomSnapshot {
+        RoomSnapshot {
+            name: self.name().to_string(),
+            devices: self.devices(),
+            subrooms: self.subrooms.iter().map(|room| room.snapshot()).collect(),
+        }
+    }
+
+    /// Возвращает имя и состояние исправности каждого подключенного
+    /// устройства в порядке подключения.
+    pub fn devices_health(&self) -> Vec<(String, DeviceHealth)> {
+        self.devices
+            .iter()
+            .map(|d| (d.name().to_string(), d.health()))
+            .collect()
+    }
+
+    /// Перемещает устройство на заданную позицию в списке устройств.
+    ///
+    /// Порядок устройств влияет на `devices()` и отчеты. Если `index`
+    /// выходит за пределы списка, устройство перемещается в конец.
+    ///
+    /// # Аргументы
+    ///
+    /// - `name`: Имя перемещаемого устройства.
+    /// - `index`: Новая позиция устройства.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// - `Ok(())` — устройство перемещено.
+    /// - `Err(SmartRoomError::DeviceNotFound)` — устройство не подключено.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Living Room"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("TV")))).unwrap();
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Lamp")))).unwrap();
+    ///
+    /// room.move_device_to("Lamp", 0).unwrap();
+    /// assert_eq!(room.devices(), vec!["Lamp".to_string(), "TV".to_string()]);
+    /// ```
+    pub fn move_device_to(&mut self, name: &str, index: usize) -> Result<(), SmartRoomError> {
+        let from = self
+            .devices
+            .iter()
+            .position(|d| self.same_name(d.name(), name))
+            .ok_or_else(|| SmartRoomError::DeviceNotFound(name.to_string()))?;
+
+        let device = self.devices.remove(from);
+        let index = index.min(self.devices.len());
+        self.devices.insert(index, device);
+
+        Ok(())
+    }
+
+    /// Упорядочивает устройства комнаты по имени.
+    pub fn sort_devices_by_name(&mut self) {
+        self.devices.sort_by(|a, b| a.name().cmp(b.name()));
+    }
+
+    /// Сравнивает комнаты по имени, набору подключенных устройств и
+    /// вложенным комнатам.
+    ///
+    /// Оператор `==` сравнивает комнаты только по имени, что удобно для
+    /// коллекций, где имя служит ключом. Этот метод дополнительно требует,
+    /// чтобы совпадали имена подключенных устройств и вложенные комнаты
+    /// попарно совпадали по `deep_eq`; порядок подключения устройств не
+    /// учитывается.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let empty = SmartRoom::new(String::from("Kitchen"));
+    /// let mut equipped = SmartRoom::new(String::from("Kitchen"));
+    /// equipped.plug(Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///
+    /// assert!(empty == equipped);
+    /// assert!(!empty.deep_eq(&equipped));
+    /// ```
+    pub fn deep_eq(&self, other: &SmartRoom) -> bool {
+        if self.name() != other.name() || self.devices.len() != other.devices.len() {
+            return false;
+        }
+
+        if self.subrooms.len() != other.subrooms.len() {
+            return false;
+        }
+
+        let mut ours = self.devices();
+        let mut theirs = other.devices();
+        ours.sort();
+        theirs.sort();
+
+        ours == theirs
+            && self
+                .subrooms
+                .iter()
+                .zip(other.subrooms.iter())
+                .all(|(a, b)| a.deep_eq(b))
+    }
+
+    /// Возвращает суммарную мощность всех устройств комнаты и вложенных
+    /// комнат в ваттах.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use lesson_4::smart::location::SmartRoom;
+    /// let room = SmartRoom::new(String::from("Empty Room"));
+    /// assert_eq!(room.power_usage(), 0.0);
+    /// ```
+    pub fn power_usage(&self) -> f64 {
+        self.total_of(|d| d.power_usage())
+    }
+
+    /// Суммирует произвольную числовую характеристику устройств комнаты,
+    /// включая устройства вложенных комнат.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap();
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+    ///
+    /// assert_eq!(room.total_of(|_| 1.0), 2.0);
+    /// ```
+    pub fn total_of<F: Fn(&dyn Pluggable) -> f64>(&self, f: F) -> f64 {
+        self.total_of_dyn(&f)
+    }
+
+    /// Рекурсивная часть `total_of`, не порождающая новых инстанциаций
+    /// для каждого уровня вложенности.
+    fn total_of_dyn(&self, f: &dyn Fn(&dyn Pluggable) -> f64) -> f64 {
+        let own: f64 = self.devices.iter().map(|d| f(d.as_ref())).sum();
+
+        own + self
+            .subrooms
+            .iter()
+            .map(|room| room.total_of_dyn(f))
+            .sum::<f64>()
+    }
+
+    /// Возвращает количество включенных устройств комнаты, включая
+    /// устройства вложенных комнат.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// let socket = Arc::new(SmartSocket::new(String::from("Toaster")));
+    /// room.plug(socket.clone()).unwrap();
+    /// assert_eq!(room.active_count(), 0);
+    ///
+    /// socket.turn_on();
+    /// assert_eq!(room.active_count(), 1);
+    /// ```
+    pub fn active_count(&self) -> usize {
+        self.walk()
+            .iter()
+            .map(|(_, room)| room.devices.iter().filter(|d| d.is_on()).count())
+            .sum()
+    }
+
+    /// Возвращает подключенные к комнате и вложенным комнатам датчики.
+    ///
+    /// Датчиками считаются устройства, для которых `Pluggable::as_sensor`
+    /// возвращает `Some`.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::{SmartSocket, SmartThermometer};
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// room.plug(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap();
+    /// room.plug(Arc::new(SmartThermometer::new(String::from("Sensor")))).unwrap();
+    ///
+    /// assert_eq!(room.sensors().len(), 1);
+    /// assert_eq!(room.sensors()[0].unit(), "°C");
+    /// ```
+    pub fn sensors(&self) -> Vec<&dyn Sensor> {
+        self.walk()
+            .into_iter()
+            .flat_map(|(_, room)| room.devices.iter().filter_map(|d| d.as_sensor()))
+            .collect()
+    }
+
+    /// Возвращает устройства комнаты и вложенных комнат, потребляемая
+    /// мощность которых превышает заданный порог.
+    ///
+    /// # Параметры
+    ///
+    /// - `threshold`: `f64` — Порог мощности в ваттах.
+    ///
+    /// # Пример
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use lesson_4::smart::location::SmartRoom;
+    /// use lesson_4::smart::device::SmartSocket;
+    ///
+    /// let mut room = SmartRoom::new(String::from("Kitchen"));
+    /// let kettle = Arc::new(SmartSocket::with_power(String::from("Kettle"), 2000.0));
+    /// kettle.turn_on();
+    /// room.plug(kettle).unwrap();
+    ///
+    /// assert_eq!(room.over_power_devices(1000.0).len(), 1);
+    /// assert!(room.over_power_devices(2000.0).is_empty());
+    /// ```
+    pub fn over_power_devices(&self, threshold: f64) -> Vec<Arc<dyn Pluggable>> {
+        self.all_devices_arc()
+            .into_iter()
+            .filter(|d| d.power_usage() > threshold)
+            .collect()
+    }
+
     /// Возвращает имя устройства.
     ///
     /// Этот метод предоставляет доступ к имени устройства,
@@ -1,8 +1,10 @@
 pub mod device;
 pub mod location;
+pub mod net;
+pub mod prelude;
 pub mod report;
 
 // Это как то феноменально, что Named сlippy помечает как unused_imports????
 #[allow(unused_imports)]
-pub use device::{Named, Pluggable};
+pub use device::{Named, Pluggable, Sensor};
 pub use report::Reportable;
@@ -0,0 +1,72 @@
+use std::io::{self, Read};
+
+/// Упаковывает команду в кадр протокола.
+///
+/// Кадр состоит из длины полезной нагрузки (`u32`, big-endian) и самих
+/// байтов команды. Ответы упаковываются тем же способом.
+///
+/// # Возвращаемое значение
+///
+/// - `Ok(Vec<u8>)` — Готовый к отправке кадр.
+/// - `Err(io::Error)` — Если длина команды не помещается в `u32`.
+///
+/// # Пример
+///
+/// ```rust
+/// use lesson_4::smart::net::encode_frame;
+/// let frame = encode_frame(b"ON").unwrap();
+/// assert_eq!(frame, vec![0, 0, 0, 2, b'O', b'N']);
+/// ```
+pub fn encode_frame(payload: &[u8]) -> io::Result<Vec<u8>> {
+    let len = u32::try_from(payload.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("frame of {} bytes is too long", payload.len()),
+        )
+    })?;
+
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+
+    Ok(frame)
+}
+
+/// Читает из потока один кадр протокола и возвращает его полезную нагрузку.
+///
+/// Чтение продолжается, пока не будут получены все байты кадра, поэтому
+/// команда, пришедшая несколькими частями, собирается целиком.
+///
+/// # Возвращаемое значение
+///
+/// - `Ok(Vec<u8>)` — Байты команды без заголовка длины.
+/// - `Err(io::Error)` — Ошибка чтения или `UnexpectedEof`, если поток
+///   закончился раньше, чем был прочитан весь кадр.
+///
+/// # Пример
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use lesson_4::smart::net::{decode_frame, encode_frame};
+///
+/// let frame = encode_frame(b"STATUS").unwrap();
+/// let payload = decode_frame(&mut Cursor::new(frame)).unwrap();
+/// assert_eq!(payload, b"STATUS");
+/// ```
+pub fn decode_frame<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+
+    let mut payload = Vec::new();
+    reader.take(len as u64).read_to_end(&mut payload)?;
+
+    if payload.len() != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("frame truncated: got {} of {} bytes", payload.len(), len),
+        ));
+    }
+
+    Ok(payload)
+}
@@ -0,0 +1,31 @@
+//! Набор часто используемых типов и трейтов библиотеки.
+//!
+//! Позволяет подключить все необходимое одной строкой.
+//!
+//! # Пример
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use lesson_4::smart::prelude::*;
+//!
+//! let mut room = SmartRoom::new(String::from("Kitchen"));
+//! room.plug(Arc::new(SmartSocket::new(String::from("Toaster")))).unwrap();
+//! room.plug(Arc::new(SmartThermometer::new(String::from("Sensor")))).unwrap();
+//!
+//! let mut house = SmartHouse::new(String::from("My Smart Home"));
+//! house.add(room).unwrap();
+//!
+//! let report = house.create_report(TreeReport).unwrap();
+//! assert!(report.contains("Toaster"));
+//! ```
+
+pub use super::device::{
+    DeviceHealth, DeviceRecord, Named, NullDevice, Pluggable, Sensor, SmartSocket,
+    SmartSocketBuilder, SmartThermometer,
+};
+pub use super::location::{
+    ChangeKind, DeviceInventoryEntry, HouseGraph, HouseSnapshot, PowerError, RoomCategory,
+    RoomSnapshot, SmartHouse, SmartHouseError, SmartRoom, SmartRoomError, SmartRoomMut,
+    ValidationIssue,
+};
+pub use super::report::{Reportable, TreeReport};
@@ -1,10 +1,95 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
 use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use super::device::{SmartSocket, SmartThermometer};
-use super::location::SmartHouse;
+use super::device::{DeviceHealth, SmartSocket, SmartThermometer};
+use super::location::{SmartHouse, SmartRoom};
+use super::{Named, Pluggable};
 
 pub trait Reportable {
     fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>>;
+
+    /// Записывает отчет в `w` вместо построения строки.
+    ///
+    /// По умолчанию строит отчет методом `make` и записывает его целиком.
+    /// Реализации могут переопределить метод, чтобы выводить большой
+    /// отчет по частям.
+    fn write_to(&self, house: &SmartHouse, w: &mut dyn Write) -> io::Result<()> {
+        let report = self
+            .make(house)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        w.write_all(report.as_bytes())
+    }
+}
+
+/// Строит отчет в строку через `write_to`.
+///
+/// Используется отчетами, которые умеют выводиться по частям, чтобы
+/// `make` и `write_to` давали одинаковый результат.
+fn collect_report<R: Reportable + ?Sized>(
+    report: &R,
+    house: &SmartHouse,
+) -> Result<String, Box<dyn Error>> {
+    let mut out = Vec::new();
+    report.write_to(house, &mut out)?;
+
+    Ok(String::from_utf8(out)?)
+}
+
+impl<R: Reportable + ?Sized> Reportable for &R {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        (**self).make(house)
+    }
+
+    fn write_to(&self, house: &SmartHouse, w: &mut dyn Write) -> io::Result<()> {
+        (**self).write_to(house, w)
+    }
+}
+
+/// Строка отчета о розетке в формате `----> Device: Socket[имя]`.
+fn socket_entry(socket: &SmartSocket) -> String {
+    format!("----> Device: Socket[{}]\n", socket.name())
+}
+
+/// Строка отчета о термометре в формате `----> Device: Thermometer[имя]`.
+fn thermometer_entry(thermometer: &SmartThermometer) -> String {
+    format!("----> Device: Thermometer[{}]\n", thermometer.name())
+}
+
+/// Отступ строк вложенной комнаты с уровнем вложенности `depth`.
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+/// Возвращает все комнаты дома вместе с вложенными в порядке обхода в
+/// глубину и путь к каждой в виде `Bedroom/Closet`.
+fn room_paths(house: &SmartHouse) -> Vec<(String, &SmartRoom)> {
+    let mut out = Vec::new();
+    let mut pending: Vec<(String, &SmartRoom)> = house
+        .get_rooms()
+        .iter()
+        .rev()
+        .map(|room| (room.name().to_string(), room))
+        .collect();
+
+    while let Some((path, room)) = pending.pop() {
+        pending.extend(
+            room.subrooms()
+                .iter()
+                .rev()
+                .map(|subroom| (format!("{}/{}", path, subroom.name()), subroom)),
+        );
+        out.push((path, room));
+    }
+
+    out
 }
 
 pub struct BorrowingDeviceInfoProvider<'a, 'b> {
@@ -17,7 +102,7 @@ impl Reportable for BorrowingDeviceInfoProvider<'_, '_> {
         let mut plugged_socket_room = None;
         let mut plugged_thermo_room = None;
 
-        for room in house.get_rooms().iter() {
+        for (_, room) in house.walk_rooms() {
             if room.is_connected(self.socket) {
                 plugged_socket_room = Some(room);
             }
@@ -33,28 +118,39 @@ impl Reportable for BorrowingDeviceInfoProvider<'_, '_> {
 
         let mut out;
 
-        if plugged_socket_room.is_some() && plugged_thermo_room.is_some() {
-            let plugged_socket_room = plugged_socket_room.unwrap();
-            let plugged_thermo_room = plugged_thermo_room.unwrap();
-
+        if let (Some(plugged_socket_room), Some(plugged_thermo_room)) =
+            (plugged_socket_room, plugged_thermo_room)
+        {
             if plugged_socket_room.name() == plugged_thermo_room.name() {
                 out = format!(
                     "{} {} {} {}",
-                    house, plugged_socket_room, self.socket, self.thermo
+                    house,
+                    plugged_socket_room,
+                    socket_entry(self.socket),
+                    thermometer_entry(self.thermo)
                 );
             } else {
                 out = format!(
                     "{} {} {} {} {}",
-                    house, plugged_socket_room, self.socket, plugged_thermo_room, self.thermo
+                    house,
+                    plugged_socket_room,
+                    socket_entry(self.socket),
+                    plugged_thermo_room,
+                    thermometer_entry(self.thermo)
                 );
             }
         } else {
             match plugged_socket_room.is_some() {
                 true => {
-                    out = format!("{} {} {}", house, plugged_socket_room.unwrap(), self.socket);
+                    out = format!(
+                        "{} {} {}",
+                        house,
+                        plugged_socket_room.unwrap(),
+                        socket_entry(self.socket)
+                    );
                 }
                 false => {
-                    out = format!("not found {}", self.socket);
+                    out = format!("not found {}", socket_entry(self.socket));
                 }
             };
 
@@ -65,11 +161,11 @@ impl Reportable for BorrowingDeviceInfoProvider<'_, '_> {
                         out,
                         house,
                         plugged_thermo_room.unwrap(),
-                        self.thermo
+                        thermometer_entry(self.thermo)
                     );
                 }
                 false => {
-                    out = format!("{} not found {}", out, self.thermo);
+                    out = format!("{} not found {}", out, thermometer_entry(self.thermo));
                 }
             }
         }
@@ -84,9 +180,9 @@ pub struct OwningDeviceInfoProvider {
 
 impl Reportable for OwningDeviceInfoProvider {
     fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
-        for room in house.get_rooms().iter() {
+        for (_, room) in house.walk_rooms() {
             if room.is_connected(&self.socket) {
-                let out = format!("{} {} {}", house, room, &self.socket);
+                let out = format!("{} {} {}", house, room, socket_entry(&self.socket));
 
                 return Ok(out);
             }
@@ -95,3 +191,1201 @@ impl Reportable for OwningDeviceInfoProvider {
         Err("Device not found".into())
     }
 }
+
+/// Отчет в виде дерева: дом, его комнаты и устройства, соединенные
+/// псевдографикой. Вложенные комнаты выводятся после устройств комнаты
+/// с дополнительным отступом.
+///
+/// ```plaintext
+/// My Smart Home
+/// ├── Kitchen
+/// │   └── Toaster
+/// └── Bedroom
+///     └── Closet
+///         └── Light
+/// ```
+pub struct TreeReport;
+
+impl Reportable for TreeReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        collect_report(self, house)
+    }
+
+    fn write_to(&self, house: &SmartHouse, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "{}", house.name())?;
+        let rooms = house.get_rooms();
+
+        for (i, room) in rooms.iter().enumerate() {
+            let last_room = i + 1 == rooms.len();
+            let (connector, indent) = match last_room {
+                true => ("└── ", "    "),
+                false => ("├── ", "│   "),
+            };
+
+            writeln!(w, "{}{}", connector, room.name())?;
+            write_room_tree(w, room, indent)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Выводит устройства и вложенные комнаты `room` как ветви дерева.
+///
+/// Устройства выводятся перед вложенными комнатами; содержимое каждой
+/// вложенной комнаты выводится с дополнительным отступом.
+fn write_room_tree(w: &mut dyn Write, room: &SmartRoom, prefix: &str) -> io::Result<()> {
+    let devices = room.devices_arc();
+    let subrooms = room.subrooms();
+    let total = devices.len() + subrooms.len();
+
+    for (j, device) in devices.iter().enumerate() {
+        let connector = match j + 1 == total {
+            true => "└── ",
+            false => "├── ",
+        };
+
+        writeln!(w, "{}{}{}", prefix, connector, device.name())?;
+    }
+
+    for (k, subroom) in subrooms.iter().enumerate() {
+        let (connector, indent) = match devices.len() + k + 1 == total {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+
+        writeln!(w, "{}{}{}", prefix, connector, subroom.name())?;
+        write_room_tree(w, subroom, &format!("{}{}", prefix, indent))?;
+    }
+
+    Ok(())
+}
+
+/// Отчет со списком всех устройств дома.
+///
+/// Каждое устройство выводится один раз вместе с комнатой, к которой оно
+/// подключено. Для устройств, подключенных к нескольким комнатам,
+/// перечисляются все такие комнаты.
+///
+/// ```plaintext
+/// -> House: My Smart Home
+/// Toaster (Kitchen)
+/// Printer (shared in: [Office, Hall])
+/// ```
+pub struct DeviceListReport;
+
+impl Reportable for DeviceListReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = format!("{}", house);
+
+        for (device, rooms) in house.distinct_devices() {
+            match rooms.len() {
+                1 => out.push_str(&format!("{} ({})\n", device.name(), rooms[0])),
+                _ => out.push_str(&format!(
+                    "{} (shared in: [{}])\n",
+                    device.name(),
+                    rooms.join(", ")
+                )),
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// Обертка, запоминающая последний построенный отчет.
+///
+/// Отчет перестраивается, когда меняется дом (другой экземпляр дома),
+/// его версия (`SmartHouse::version`) или состояние устройств, которое
+/// версия не отражает: включение, мощность, исправность и время
+/// последнего замера. Ошибки не кэшируются.
+///
+/// ```rust
+/// use lesson_4::smart::location::SmartHouse;
+/// use lesson_4::smart::report::{CachedReport, TreeReport};
+///
+/// let house = SmartHouse::new(String::from("My Smart Home"));
+/// let report = CachedReport::new(TreeReport);
+///
+/// assert_eq!(house.create_report(&report).unwrap(), "My Smart Home\n");
+/// ```
+pub struct CachedReport<T: Reportable> {
+    inner: T,
+    cache: RefCell<Option<(CacheKey, String)>>,
+}
+
+/// Ключ кэша `CachedReport`: экземпляр дома, его версия и отпечаток
+/// состояния устройств.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CacheKey {
+    house: u64,
+    version: u64,
+    devices: u64,
+}
+
+impl CacheKey {
+    fn of(house: &SmartHouse) -> Self {
+        let mut hasher = DefaultHasher::new();
+        let mut seen: HashSet<*const ()> = HashSet::new();
+
+        for (_, device) in house.all_devices_with_room() {
+            if !seen.insert(Arc::as_ptr(device) as *const ()) {
+                continue;
+            }
+
+            device.is_on().hash(&mut hasher);
+            device.power_usage().to_bits().hash(&mut hasher);
+            device.health().hash(&mut hasher);
+            device.last_seen().hash(&mut hasher);
+        }
+
+        Self {
+            house: house.id(),
+            version: house.version(),
+            devices: hasher.finish(),
+        }
+    }
+}
+
+impl<T: Reportable> CachedReport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            cache: RefCell::new(None),
+        }
+    }
+
+    /// Сбрасывает сохраненный отчет.
+    pub fn invalidate(&self) {
+        self.cache.borrow_mut().take();
+    }
+}
+
+impl<T: Reportable> Reportable for CachedReport<T> {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let key = CacheKey::of(house);
+
+        if let Some((cached, report)) = self.cache.borrow().as_ref() {
+            if *cached == key {
+                return Ok(report.clone());
+            }
+        }
+
+        let report = self.inner.make(house)?;
+        *self.cache.borrow_mut() = Some((key, report.clone()));
+
+        Ok(report)
+    }
+}
+
+/// Отчет, объединяющий несколько других отчетов.
+///
+/// Отчеты строятся по порядку и разделяются пустой строкой. Если один
+/// из отчетов завершился ошибкой, остальные не строятся и возвращается
+/// эта ошибка.
+pub struct CombinedReport {
+    reports: Vec<Box<dyn Reportable>>,
+}
+
+impl CombinedReport {
+    pub fn new(reports: Vec<Box<dyn Reportable>>) -> Self {
+        Self { reports }
+    }
+}
+
+impl Reportable for CombinedReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = String::new();
+
+        for (i, report) in self.reports.iter().enumerate() {
+            if i > 0 {
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+
+            out.push_str(&report.make(house)?);
+        }
+
+        Ok(out)
+    }
+}
+
+/// Отчет в формате HTML для встраивания в веб-интерфейс.
+///
+/// Дом выводится заголовком `<h1>`, комнаты — заголовками `<h2>`,
+/// устройства — списком `<ul>`. Вложенные комнаты следуют за своей
+/// комнатой с заголовком на уровень ниже (не ниже `<h6>`). Все имена
+/// экранируются.
+///
+/// ```rust
+/// use lesson_4::smart::location::SmartHouse;
+/// use lesson_4::smart::report::HtmlReport;
+///
+/// let house = SmartHouse::new(String::from("Tom & Jerry"));
+/// let report = house.create_report(HtmlReport).unwrap();
+/// assert!(report.contains("<h1>Tom &amp; Jerry</h1>"));
+/// ```
+pub struct HtmlReport;
+
+impl Reportable for HtmlReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = String::from("<html>\n<body>\n");
+        out.push_str(&format!("<h1>{}</h1>\n", html_escape(house.name())));
+
+        for (depth, room) in house.walk_rooms() {
+            let level = (2 + depth).min(6);
+            out.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                level,
+                html_escape(room.name()),
+                level
+            ));
+
+            let devices = room.devices();
+
+            if devices.is_empty() {
+                continue;
+            }
+
+            out.push_str("<ul>\n");
+            for device in devices.iter() {
+                out.push_str(&format!("<li>{}</li>\n", html_escape(device)));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        out.push_str("</body>\n</html>\n");
+
+        Ok(out)
+    }
+}
+
+/// Экранирует специальные символы HTML.
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Отчет, построенный только по комнатам, имена которых удовлетворяют
+/// условию.
+///
+/// Внутренний отчет получает временную копию дома, в которой оставлены
+/// только подходящие комнаты.
+///
+/// ```rust
+/// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+/// use lesson_4::smart::report::{FilteredReport, TreeReport};
+///
+/// let mut house = SmartHouse::new(String::from("My Smart Home"));
+/// house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+/// house.add(SmartRoom::new(String::from("Bedroom"))).unwrap();
+///
+/// let report = FilteredReport::new(TreeReport, |name: &str| name != "Kitchen");
+/// assert_eq!(house.create_report(report).unwrap(), "My Smart Home\n└── Bedroom\n");
+/// ```
+pub struct FilteredReport<T: Reportable, F: Fn(&str) -> bool> {
+    inner: T,
+    predicate: F,
+}
+
+impl<T: Reportable, F: Fn(&str) -> bool> FilteredReport<T, F> {
+    pub fn new(inner: T, predicate: F) -> Self {
+        Self { inner, predicate }
+    }
+}
+
+impl<T: Reportable, F: Fn(&str) -> bool> Reportable for FilteredReport<T, F> {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let filtered = house.filter_rooms(|room| (self.predicate)(room.name()));
+
+        self.inner.make(&filtered)
+    }
+}
+
+/// Отчет о неисправных устройствах дома.
+///
+/// Перечисляет по комнатам все устройства, состояние которых отлично от
+/// `DeviceHealth::Ok`. Строки вложенных комнат выводятся с отступом в
+/// четыре пробела на каждый уровень вложенности. Если таких устройств
+/// нет, отчет состоит из строки "All systems nominal".
+///
+/// ```plaintext
+/// -> House: My Smart Home
+/// --> Room: Kitchen
+/// Kettle: fault: heating element broken
+/// ```
+pub struct HealthReport;
+
+impl Reportable for HealthReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = format!("{}", house);
+        let mut nominal = true;
+
+        for (depth, room) in house.walk_rooms() {
+            let issues: Vec<(String, DeviceHealth)> = room
+                .devices_health()
+                .into_iter()
+                .filter(|(_, health)| *health != DeviceHealth::Ok)
+                .collect();
+
+            if issues.is_empty() {
+                continue;
+            }
+
+            nominal = false;
+            out.push_str(&format!("{}{}", indent(depth), room));
+
+            for (device, health) in issues {
+                out.push_str(&format!("{}{}: {}\n", indent(depth), device, health));
+            }
+        }
+
+        if nominal {
+            out.push_str("All systems nominal\n");
+        }
+
+        Ok(out)
+    }
+}
+
+/// Отчет с отметкой времени создания и длительностью построения.
+///
+/// Перед отчетом `inner` выводится строка с временем создания в UTC,
+/// после него — строка с длительностью построения в миллисекундах.
+///
+/// ```plaintext
+/// generated at 2024-05-01 12:00:00 UTC
+/// My Smart Home
+/// generated in 0 ms
+/// ```
+pub struct TimedReport<T: Reportable> {
+    inner: T,
+}
+
+impl<T: Reportable> TimedReport<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Reportable> Reportable for TimedReport<T> {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let started = Instant::now();
+        let mut out = format!("generated at {}\n", format_utc(SystemTime::now()));
+
+        out.push_str(&self.inner.make(house)?);
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "generated in {} ms\n",
+            started.elapsed().as_millis()
+        ));
+
+        Ok(out)
+    }
+}
+
+/// Форматирует время как `YYYY-MM-DD HH:MM:SS UTC`.
+fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Перевод числа дней с начала эпохи в григорианскую дату.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+/// Отчет в формате GraphViz DOT.
+///
+/// Дом является корнем графа, комнаты — его потомками, вложенные
+/// комнаты — потомками своих комнат, устройства — листьями. Общее
+/// устройство, подключенное к нескольким комнатам, изображается одним
+/// узлом с ребрами из каждой комнаты.
+///
+/// ```plaintext
+/// digraph house {
+///     house [label="My Smart Home"];
+///     room0 [label="Kitchen"];
+///     house -> room0;
+///     device0 [label="Kettle"];
+///     room0 -> device0;
+/// }
+/// ```
+pub struct DotReport;
+
+impl Reportable for DotReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = String::from("digraph house {\n");
+        out.push_str(&format!(
+            "    house [label=\"{}\"];\n",
+            dot_escape(house.name())
+        ));
+
+        let rooms = house.walk_rooms();
+        let mut parents: Vec<usize> = Vec::new();
+
+        for (i, (depth, room)) in rooms.iter().enumerate() {
+            out.push_str(&format!(
+                "    room{} [label=\"{}\"];\n",
+                i,
+                dot_escape(room.name())
+            ));
+
+            parents.truncate(*depth);
+            match parents.last() {
+                Some(parent) => out.push_str(&format!("    room{} -> room{};\n", parent, i)),
+                None => out.push_str(&format!("    house -> room{};\n", i)),
+            }
+            parents.push(i);
+        }
+
+        let devices = house.distinct_devices();
+
+        for (i, (device, _)) in devices.iter().enumerate() {
+            out.push_str(&format!(
+                "    device{} [label=\"{}\"];\n",
+                i,
+                dot_escape(device.name())
+            ));
+
+            for (r, (_, room)) in rooms.iter().enumerate() {
+                if room.devices_arc().iter().any(|d| Arc::ptr_eq(d, device)) {
+                    out.push_str(&format!("    room{} -> device{};\n", r, i));
+                }
+            }
+        }
+
+        out.push_str("}\n");
+
+        Ok(out)
+    }
+}
+
+/// Экранирует строку для использования внутри кавычек в DOT.
+fn dot_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Отчет в формате JSON.
+///
+/// Дом выводится объектом с именем и массивом комнат, каждая комната —
+/// объектом с именем и массивом имен устройств. У комнаты с вложенными
+/// комнатами есть также поле `subrooms` с массивом комнат того же вида.
+///
+/// ```plaintext
+/// {"name":"My Smart Home","rooms":[{"name":"Kitchen","devices":["Kettle"]}]}
+/// ```
+pub struct JsonReport;
+
+impl Reportable for JsonReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        collect_report(self, house)
+    }
+
+    fn write_to(&self, house: &SmartHouse, w: &mut dyn Write) -> io::Result<()> {
+        write!(
+            w,
+            "{{\"name\":\"{}\",\"rooms\":[",
+            json_escape(house.name())
+        )?;
+
+        write_json_rooms(w, house.get_rooms())?;
+
+        writeln!(w, "]}}")
+    }
+}
+
+/// Записывает комнаты через запятую, вложенные комнаты — рекурсивно в
+/// поле `subrooms`, если они есть.
+fn write_json_rooms(w: &mut dyn Write, rooms: &[SmartRoom]) -> io::Result<()> {
+    for (i, room) in rooms.iter().enumerate() {
+        if i > 0 {
+            write!(w, ",")?;
+        }
+
+        write!(
+            w,
+            "{{\"name\":\"{}\",\"devices\":[",
+            json_escape(room.name())
+        )?;
+
+        for (j, device) in room.devices_arc().iter().enumerate() {
+            if j > 0 {
+                write!(w, ",")?;
+            }
+
+            write!(w, "\"{}\"", json_escape(device.name()))?;
+        }
+
+        write!(w, "]")?;
+
+        if !room.subrooms().is_empty() {
+            write!(w, ",\"subrooms\":[")?;
+            write_json_rooms(w, room.subrooms())?;
+            write!(w, "]")?;
+        }
+
+        write!(w, "}}")?;
+    }
+
+    Ok(())
+}
+
+/// Экранирует строку для использования внутри кавычек в JSON.
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Отчет в формате CSV: строка заголовка и по строке на каждое
+/// устройство каждой комнаты. Вложенная комната указывается путем от
+/// комнаты дома через `/`.
+///
+/// ```plaintext
+/// room,device
+/// Kitchen,Kettle
+/// Bedroom/Closet,Light
+/// ```
+pub struct CsvReport;
+
+impl Reportable for CsvReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        collect_report(self, house)
+    }
+
+    fn write_to(&self, house: &SmartHouse, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "room,device")?;
+
+        for (path, room) in room_paths(house) {
+            for device in room.devices_arc().iter() {
+                writeln!(w, "{},{}", csv_escape(&path), csv_escape(device.name()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Заключает поле CSV в кавычки, если оно содержит разделитель, кавычки
+/// или перевод строки.
+fn csv_escape(s: &str) -> String {
+    match s.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_string(),
+    }
+}
+
+/// Отчет в формате Markdown.
+///
+/// Вложенные комнаты выводятся заголовками на уровень ниже своей
+/// комнаты (не ниже шестого уровня).
+///
+/// ```plaintext
+/// # My Smart Home
+///
+/// ## Kitchen
+///
+/// - Kettle
+/// ```
+pub struct MarkdownReport;
+
+impl Reportable for MarkdownReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        collect_report(self, house)
+    }
+
+    fn write_to(&self, house: &SmartHouse, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "# {}", house.name())?;
+
+        for (depth, room) in house.walk_rooms() {
+            writeln!(w, "\n{} {}", "#".repeat((2 + depth).min(6)), room.name())?;
+
+            let devices = room.devices_arc();
+
+            if devices.is_empty() {
+                continue;
+            }
+
+            writeln!(w)?;
+            for device in devices.iter() {
+                writeln!(w, "- {}", device.name())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Формат отчета, выбираемый во время выполнения, например, по флагу
+/// командной строки.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+    Markdown,
+}
+
+/// Создает отчет заданного формата.
+///
+/// `Text` соответствует `TreeReport`, остальные форматы — `JsonReport`,
+/// `CsvReport` и `MarkdownReport`.
+///
+/// ```rust
+/// use lesson_4::smart::location::SmartHouse;
+/// use lesson_4::smart::report::{make_report, ReportFormat};
+///
+/// let house = SmartHouse::new(String::from("My Smart Home"));
+/// let report = make_report(ReportFormat::Text);
+/// assert_eq!(house.create_report_with(report.as_ref()).unwrap(), "My Smart Home\n");
+/// ```
+pub fn make_report(format: ReportFormat) -> Box<dyn Reportable> {
+    match format {
+        ReportFormat::Text => Box::new(TreeReport),
+        ReportFormat::Json => Box::new(JsonReport),
+        ReportFormat::Csv => Box::new(CsvReport),
+        ReportFormat::Markdown => Box::new(MarkdownReport),
+    }
+}
+
+/// Единица измерения мощности в отчетах.
+///
+/// `Auto` подбирает единицу для каждого значения функцией `format_power`
+/// и используется по умолчанию.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowerUnit {
+    Watts,
+    Kilowatts,
+    #[default]
+    Auto,
+}
+
+impl PowerUnit {
+    /// Форматирует мощность, заданную в ваттах, в этой единице,
+    /// например `1500 W` или `1.5 kW`. Значение округляется так же, как
+    /// в `format_power`.
+    pub fn format(&self, watts: f64) -> String {
+        match self {
+            Self::Watts => format!("{} W", round_power(watts)),
+            Self::Kilowatts => format!("{} kW", round_power(watts / 1000.0)),
+            Self::Auto => format_power(watts),
+        }
+    }
+}
+
+/// Округляет значение мощности до одного знака после запятой.
+///
+/// Отрицательный ноль заменяется нулем, чтобы не выводить `-0`.
+fn round_power(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0 + 0.0
+}
+
+/// Форматирует мощность в ваттах в удобном для чтения виде.
+///
+/// Единица (W, kW или MW) выбирается по величине значения, значение
+/// округляется до одного знака после запятой; нулевая дробная часть не
+/// выводится.
+///
+/// # Пример
+///
+/// ```
+/// use lesson_4::smart::report::format_power;
+///
+/// assert_eq!(format_power(230.0), "230 W");
+/// assert_eq!(format_power(1500.0), "1.5 kW");
+/// assert_eq!(format_power(2_000_000.0), "2 MW");
+/// ```
+pub fn format_power(watts: f64) -> String {
+    // Единица выбирается по уже округленному значению, чтобы 999.96 W
+    // выводилось как 1 kW, а не 1000 W.
+    let (value, unit) = match watts.abs() {
+        w if round_power(w / 1_000.0) >= 1_000.0 => (watts / 1_000_000.0, "MW"),
+        w if round_power(w) >= 1_000.0 => (watts / 1_000.0, "kW"),
+        _ => (watts, "W"),
+    };
+
+    format!("{} {}", round_power(value), unit)
+}
+
+/// Отчет о потребляемой мощности устройств.
+///
+/// Для каждой комнаты перечисляется мощность ее устройств, в конце
+/// выводится суммарная мощность дома. Строки вложенных комнат выводятся
+/// с отступом в четыре пробела на каждый уровень вложенности, их
+/// устройства входят в итог. Общее устройство учитывается в итоге один
+/// раз. Отчет по умолчанию (`EnergyReport::default()`)
+/// форматирует мощность функцией `format_power`.
+///
+/// ```plaintext
+/// -> House: My Smart Home
+/// --> Room: Kitchen
+/// Kettle: 1.5 kW
+/// Total: 1.5 kW
+/// ```
+pub struct EnergyReport {
+    unit: PowerUnit,
+}
+
+impl EnergyReport {
+    pub fn new(unit: PowerUnit) -> Self {
+        Self { unit }
+    }
+}
+
+impl Default for EnergyReport {
+    /// Отчет с автоматическим выбором единицы (`PowerUnit::Auto`).
+    fn default() -> Self {
+        Self::new(PowerUnit::default())
+    }
+}
+
+impl Reportable for EnergyReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = format!("{}", house);
+
+        for (depth, room) in house.walk_rooms() {
+            out.push_str(&format!("{}{}", indent(depth), room));
+
+            for device in room.devices_arc().iter() {
+                out.push_str(&format!(
+                    "{}{}: {}\n",
+                    indent(depth),
+                    device.name(),
+                    self.unit.format(device.power_usage())
+                ));
+            }
+        }
+
+        let total: f64 = house
+            .distinct_devices()
+            .iter()
+            .map(|(d, _)| d.power_usage())
+            .sum();
+        out.push_str(&format!("Total: {}\n", self.unit.format(total)));
+
+        Ok(out)
+    }
+}
+
+/// Отчет об изменениях дома относительно сохраненного состояния.
+///
+/// Перечисляет добавленные и удаленные комнаты, а также добавленные и
+/// отключенные устройства в виде `комната: устройство`. Вложенные
+/// комнаты указываются путем от комнаты дома через `/`. Пустые разделы
+/// не выводятся; если изменений нет, выводится "No changes".
+///
+/// ```plaintext
+/// + rooms
+/// Garage
+/// + devices
+/// Garage: Charger
+/// Kitchen: Kettle
+/// - devices
+/// Hall: Lamp
+/// ```
+pub struct DiffReport {
+    pub baseline: SmartHouse,
+}
+
+impl Reportable for DiffReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        fn pairs(house: &SmartHouse) -> Vec<String> {
+            room_paths(house)
+                .into_iter()
+                .flat_map(|(path, room)| {
+                    room.devices()
+                        .into_iter()
+                        .map(move |device| format!("{}: {}", path, device))
+                })
+                .collect()
+        }
+
+        fn missing<'a>(from: &'a [String], other: &[String]) -> Vec<&'a String> {
+            from.iter().filter(|item| !other.contains(item)).collect()
+        }
+
+        let rooms: Vec<String> = room_paths(house).into_iter().map(|(p, _)| p).collect();
+        let base_rooms: Vec<String> = room_paths(&self.baseline)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        let devices = pairs(house);
+        let base_devices = pairs(&self.baseline);
+
+        let sections = [
+            ("+ rooms", missing(&rooms, &base_rooms)),
+            ("- rooms", missing(&base_rooms, &rooms)),
+            ("+ devices", missing(&devices, &base_devices)),
+            ("- devices", missing(&base_devices, &devices)),
+        ];
+
+        let mut out = String::new();
+
+        for (title, items) in sections.iter() {
+            if items.is_empty() {
+                continue;
+            }
+
+            out.push_str(&format!("{}\n", title));
+            for item in items.iter() {
+                out.push_str(&format!("{}\n", item));
+            }
+        }
+
+        if out.is_empty() {
+            out.push_str("No changes\n");
+        }
+
+        Ok(out)
+    }
+}
+
+/// Отчет об оценочной стоимости работы устройств.
+///
+/// Для каждой комнаты перечисляется стоимость работы ее устройств за
+/// `hours` часов по цене `price_per_kwh` (см. `Pluggable::energy_cost`),
+/// в конце выводится итог по дому. Вложенные комнаты выводятся с отступом
+/// так же, как в `EnergyReport`, и входят в итог. Общее устройство
+/// учитывается в итоге один раз. Суммы округляются до сотых.
+///
+/// ```plaintext
+/// -> House: My Smart Home
+/// --> Room: Kitchen
+/// Kettle: 0.40
+/// Total: 0.40
+/// ```
+pub struct CostReport {
+    price_per_kwh: f64,
+    hours: f64,
+}
+
+impl CostReport {
+    pub fn new(price_per_kwh: f64, hours: f64) -> Self {
+        Self {
+            price_per_kwh,
+            hours,
+        }
+    }
+}
+
+impl Reportable for CostReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut out = format!("{}", house);
+
+        for (depth, room) in house.walk_rooms() {
+            out.push_str(&format!("{}{}", indent(depth), room));
+
+            for device in room.devices_arc().iter() {
+                out.push_str(&format!(
+                    "{}{}: {:.2}\n",
+                    indent(depth),
+                    device.name(),
+                    device.energy_cost(self.price_per_kwh, self.hours)
+                ));
+            }
+        }
+
+        let total: f64 = house
+            .distinct_devices()
+            .iter()
+            .map(|(d, _)| d.energy_cost(self.price_per_kwh, self.hours))
+            .sum();
+        out.push_str(&format!("Total: {:.2}\n", total));
+
+        Ok(out)
+    }
+}
+
+/// Отчет о количестве устройств каждого вида (`Pluggable::kind`).
+///
+/// Виды выводятся в алфавитном порядке. Общее устройство учитывается
+/// один раз.
+///
+/// ```plaintext
+/// socket: 4
+/// thermometer: 2
+/// ```
+pub struct KindSummaryReport;
+
+impl Reportable for KindSummaryReport {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+
+        for (device, _) in house.distinct_devices().iter() {
+            *counts.entry(device.kind()).or_default() += 1;
+        }
+
+        let mut out = String::new();
+        for (kind, count) in counts.iter() {
+            out.push_str(&format!("{}: {}\n", kind, count));
+        }
+
+        Ok(out)
+    }
+}
+
+/// Отчет, скрывающий имена устройств.
+///
+/// Перед построением отчета `inner` имена устройств заменяются
+/// обозначениями `device_<n>` с нумерацией от единицы в порядке обхода
+/// комнат. Общее устройство получает один номер и остается общим для
+/// всех своих комнат. Структура дома, мощность, состояние и тип устройств
+/// сохраняются.
+///
+/// # Пример
+///
+/// ```
+/// use std::sync::Arc;
+/// use lesson_4::smart::device::SmartSocket;
+/// use lesson_4::smart::location::{SmartHouse, SmartRoom};
+/// use lesson_4::smart::report::{RedactedReport, TreeReport};
+///
+/// let mut house = SmartHouse::new(String::from("My Smart Home"));
+/// house.add(SmartRoom::new(String::from("Kitchen"))).unwrap();
+/// house.plug("Kitchen", Arc::new(SmartSocket::new(String::from("Kettle")))).unwrap();
+///
+/// let report = house.create_report(RedactedReport::new(TreeReport)).unwrap();
+/// assert!(report.contains("device_1"));
+/// assert!(!report.contains("Kettle"));
+/// ```
+pub struct RedactedReport<T: Reportable> {
+    inner: T,
+}
+
+impl<T: Reportable> RedactedReport<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Reportable> Reportable for RedactedReport<T> {
+    fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+        let mut redacted = SmartHouse::new(house.name().to_string());
+        let mut devices = Vec::new();
+
+        for room in house.get_rooms().iter() {
+            redacted.add(redact_room(room, &mut devices)?)?;
+        }
+
+        self.inner.make(&redacted)
+    }
+}
+
+/// Пары (исходное устройство, его замена) в порядке нумерации.
+type RedactedDevices = Vec<(Arc<dyn Pluggable>, Arc<dyn Pluggable>)>;
+
+/// Копирует комнату вместе с вложенными комнатами, заменяя устройства
+/// на `RedactedDevice` со сквозной нумерацией.
+///
+/// Уже замененное устройство (общее для нескольких комнат) подключается
+/// той же заменой из `devices`.
+fn redact_room(
+    room: &SmartRoom,
+    devices: &mut RedactedDevices,
+) -> Result<SmartRoom, Box<dyn Error>> {
+    let mut copy = SmartRoom::with_category(room.name().to_string(), room.category());
+
+    for device in room.devices_arc().iter() {
+        let redacted = match devices.iter().find(|(d, _)| Arc::ptr_eq(d, device)) {
+            Some((_, redacted)) => Arc::clone(redacted),
+            None => {
+                let redacted: Box<dyn Pluggable> = Box::new(RedactedDevice {
+                    name: format!("device_{}", devices.len() + 1),
+                    inner: Arc::clone(device),
+                });
+                let redacted: Arc<dyn Pluggable> = Arc::from(redacted);
+                devices.push((Arc::clone(device), Arc::clone(&redacted)));
+
+                redacted
+            }
+        };
+
+        copy.plug(redacted)?;
+    }
+
+    for subroom in room.subrooms().iter() {
+        copy.add_subroom(redact_room(subroom, devices)?)?;
+    }
+
+    Ok(copy)
+}
+
+/// Устройство с подмененным именем для `RedactedReport`.
+///
+/// Все методы, кроме имени, описания и метаданных, делегируются исходному
+/// устройству.
+struct RedactedDevice {
+    name: String,
+    inner: Arc<dyn Pluggable>,
+}
+
+impl Named for RedactedDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Pluggable for RedactedDevice {
+    fn power_usage(&self) -> f64 {
+        self.inner.power_usage()
+    }
+
+    fn is_on(&self) -> bool {
+        self.inner.is_on()
+    }
+
+    fn health(&self) -> DeviceHealth {
+        self.inner.health()
+    }
+
+    fn kind(&self) -> &'static str {
+        self.inner.kind()
+    }
+}
+
+/// Ошибки разбора текстового отчета функцией `parse_text_report`.
+///
+/// # Варианты
+///
+/// - `DeviceOutsideRoom(usize)` — строка устройства встретилась раньше
+///   первой строки комнаты. Содержит номер строки, начиная с единицы.
+/// - `MalformedLine { line, text }` — строка не соответствует формату
+///   текстового отчета.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    DeviceOutsideRoom(usize),
+    MalformedLine { line: usize, text: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeviceOutsideRoom(line) => write!(f, "line {}: device outside of a room", line),
+            Self::MalformedLine { line, text } => write!(f, "line {}: malformed {:?}", line, text),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Восстанавливает имена комнат и устройств из текстового отчета.
+///
+/// Понимает строки вида `-> House: ...`, `--> Room: ...` и
+/// `----> Device: Kind[Name]`, в том числе с ведущими пробелами, как в
+/// отчетах `OwningDeviceInfoProvider` и `BorrowingDeviceInfoProvider`.
+/// Строка дома пропускается, пустые строки игнорируются. Устройства
+/// повторно встретившейся комнаты добавляются к уже найденным.
+///
+/// ```rust
+/// use lesson_4::smart::report::parse_text_report;
+///
+/// let rooms = parse_text_report("-> House: Home\n --> Room: Kitchen\n ----> Device: Socket[Kettle]\n").unwrap();
+/// assert_eq!(rooms, vec![(String::from("Kitchen"), vec![String::from("Kettle")])]);
+/// ```
+pub fn parse_text_report(s: &str) -> Result<Vec<(String, Vec<String>)>, ParseError> {
+    let mut rooms: Vec<(String, Vec<String>)> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for (i, raw) in s.lines().enumerate() {
+        let line = raw.trim();
+        let malformed = || ParseError::MalformedLine {
+            line: i + 1,
+            text: raw.to_string(),
+        };
+
+        if line.is_empty() || line.starts_with("-> House:") {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("--> Room:") {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(malformed());
+            }
+
+            current = match rooms.iter().position(|(room, _)| room == name) {
+                Some(index) => Some(index),
+                None => {
+                    rooms.push((name.to_string(), Vec::new()));
+                    Some(rooms.len() - 1)
+                }
+            };
+        } else if let Some(device) = line.strip_prefix("----> Device:") {
+            let device = device.trim();
+            let name = match (device.find('['), device.strip_suffix(']')) {
+                (Some(open), Some(inner)) => &inner[open + 1..],
+                _ => device,
+            };
+
+            if name.is_empty() {
+                return Err(malformed());
+            }
+
+            let index = current.ok_or(ParseError::DeviceOutsideRoom(i + 1))?;
+            rooms[index].1.push(name.to_string());
+        } else {
+            return Err(malformed());
+        }
+    }
+
+    Ok(rooms)
+}
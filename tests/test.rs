@@ -1,12 +1,29 @@
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-use lesson_4::smart::device::{SmartSocket, SmartThermometer};
-use lesson_4::smart::location::{SmartHouse, SmartRoom};
-use lesson_4::smart::report::{BorrowingDeviceInfoProvider, OwningDeviceInfoProvider};
+use lesson_4::smart::device::{
+    DeviceHealth, DeviceRecord, NullDevice, Schedule, Sensor, SmartSocket, SmartSocketBuilder,
+    SmartThermometer, Temperature,
+};
+use lesson_4::smart::location::{
+    ChangeKind, DeviceInventoryEntry, HouseGraph, HouseSnapshot, PowerError, RoomCategory,
+    RoomSnapshot, SmartHouse, SmartHouseError, SmartRoom, SmartRoomError, ValidationIssue,
+};
+use lesson_4::smart::net::{decode_frame, encode_frame};
+use lesson_4::smart::report::{
+    format_power, make_report, parse_text_report, BorrowingDeviceInfoProvider, CachedReport,
+    CombinedReport, CostReport, DeviceListReport, DiffReport, DotReport, EnergyReport,
+    FilteredReport, HealthReport, HtmlReport, KindSummaryReport, OwningDeviceInfoProvider,
+    ParseError, PowerUnit, RedactedReport, ReportFormat, TimedReport, TreeReport,
+};
+use lesson_4::smart::{Named, Pluggable, Reportable};
 
 #[cfg(test)]
 mod tests {
-    use custom::Robot;
+    use custom::{Broken, CountingReport, Heater, Robot};
 
     use super::*;
 
@@ -22,7 +39,7 @@ mod tests {
         let limb = SmartRoom::new("limb".to_string());
         assert!(hell.add(limb).is_err(), "Limb has already been added");
 
-        hell.del("limb");
+        assert!(hell.del("limb").is_ok(), "Limb can be removed");
 
         assert!(hell.get_rooms().eq(&[SmartRoom::new("lust".to_string())]),);
     }
@@ -76,6 +93,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn info_providers_find_devices_in_subrooms() {
+        let thermo = SmartThermometer::new("Thermo".to_string());
+        let socket = SmartSocket::new("Light".to_string());
+
+        let mut closet = SmartRoom::new("Closet".to_string());
+        closet.plug(Arc::new(socket.clone())).unwrap();
+        closet.plug(Arc::new(thermo.clone())).unwrap();
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        bedroom.add_subroom(closet).unwrap();
+
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(bedroom).unwrap();
+
+        let borrowing = house
+            .create_report(BorrowingDeviceInfoProvider {
+                socket: &socket,
+                thermo: &thermo,
+            })
+            .unwrap();
+        assert_eq!(
+            borrowing,
+            "-> House: Home\n --> Room: Closet\n ----> Device: Socket[Light]\n ----> Device: Thermometer[Thermo]\n"
+        );
+
+        let owning = house
+            .create_report(OwningDeviceInfoProvider { socket })
+            .unwrap();
+        assert_eq!(
+            owning,
+            "-> House: Home\n --> Room: Closet\n ----> Device: Socket[Light]\n"
+        );
+    }
+
     #[test]
     fn report_borrowing_report_provider() {
         let mut house = SmartHouse::new("Millennium Falcon".to_string());
@@ -126,24 +177,2372 @@ mod tests {
         );
     }
 
-    pub mod custom {
-        use lesson_4::smart::{Named, Pluggable};
-        pub struct Robot {
-            name: String,
+    #[test]
+    fn room_from_devices() {
+        let devices: Vec<Arc<dyn Pluggable>> = vec![
+            Arc::new(SmartThermometer::new("Main thermometer".to_string())),
+            Arc::new(SmartSocket::new("Main socket".to_string())),
+        ];
+
+        let room = SmartRoom::try_from_devices("Boiler".to_string(), devices)
+            .expect("All device names are unique");
+
+        assert_eq!(room.name(), "Boiler");
+        assert_eq!(room.devices(), vec!["Main thermometer", "Main socket"]);
+    }
+
+    #[test]
+    fn room_from_devices_with_duplicates() {
+        let devices: Vec<Arc<dyn Pluggable>> = vec![
+            Arc::new(SmartSocket::new("Main socket".to_string())),
+            Arc::new(SmartThermometer::new("Main thermometer".to_string())),
+            Arc::new(SmartSocket::new("Main socket".to_string())),
+        ];
+
+        match SmartRoom::try_from_devices("Boiler".to_string(), devices) {
+            Err(e) => assert_eq!(
+                e,
+                SmartRoomError::DeviceAlreadyPlugged("Main socket".to_string())
+            ),
+            Ok(_) => panic!("Duplicate socket should not be plugged"),
         }
+    }
 
-        impl Robot {
-            pub fn new(name: String) -> Self {
-                Self { name }
+    #[test]
+    fn rename_house() {
+        let mut house = SmartHouse::new("Template".to_string());
+
+        house.set_name("Flat 42".to_string());
+
+        assert_eq!(house.name(), "Flat 42");
+        assert_eq!(house.to_string(), "-> House: Flat 42\n");
+    }
+
+    #[test]
+    fn find_all_devices() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        let hall = SmartRoom::new("Hall".to_string());
+
+        let _ = kitchen.plug(Arc::new(SmartThermometer::new("Sensor".to_string())));
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = bedroom.plug(Arc::new(SmartThermometer::new("Sensor".to_string())));
+
+        let _ = house.add(kitchen);
+        let _ = house.add(hall);
+        let _ = house.add(bedroom);
+
+        let found = house.find_all_devices("Sensor");
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].0.name(), "Kitchen");
+        assert_eq!(found[1].0.name(), "Bedroom");
+        assert!(found.iter().all(|(_, device)| device.name() == "Sensor"));
+
+        assert!(house.find_all_devices("Toaster").is_empty());
+    }
+
+    #[test]
+    fn report_tree() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = bedroom.plug(Arc::new(SmartThermometer::new("Sensor".to_string())));
+
+        let _ = house.add(kitchen);
+        let _ = house.add(bedroom);
+
+        let report = house.create_report(TreeReport).unwrap();
+        let expected = "Sweet home
+├── Kitchen
+│   ├── Toaster
+│   └── Kettle
+└── Bedroom
+    └── Sensor
+";
+
+        assert_eq!(report, expected, "Compare got and expected reports");
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        let frame = encode_frame(b"STATUS").unwrap();
+        assert_eq!(&frame[..4], &[0, 0, 0, 6]);
+
+        let mut stream = Cursor::new(frame);
+        assert_eq!(decode_frame(&mut stream).unwrap(), b"STATUS");
+
+        // Длина больше 255 байт занимает несколько байт заголовка.
+        let command = "X".repeat(0x0102);
+        let frame = encode_frame(command.as_bytes()).unwrap();
+        assert_eq!(&frame[..4], &[0, 0, 1, 2]);
+
+        let mut stream = Cursor::new(frame);
+        assert_eq!(decode_frame(&mut stream).unwrap(), command.as_bytes());
+    }
+
+    #[test]
+    fn frame_truncated() {
+        let mut frame = encode_frame(b"TURN ON").unwrap();
+        frame.truncate(6);
+
+        let err = decode_frame(&mut Cursor::new(frame)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rooms_by_power() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        let mut garage = SmartRoom::new("Garage".to_string());
+        let mut attic = SmartRoom::new("Attic".to_string());
+
+        let _ = kitchen.plug(Arc::new(Heater::new("Oven".to_string(), 2000.0)));
+        let _ = kitchen.plug(Arc::new(Heater::new("Kettle".to_string(), 1500.0)));
+        let _ = bedroom.plug(Arc::new(Heater::new("Lamp".to_string(), 60.0)));
+        let _ = garage.plug(Arc::new(Heater::new("Charger".to_string(), 3000.0)));
+        let _ = attic.plug(Arc::new(Heater::new("Fan".to_string(), 60.0)));
+
+        let _ = house.add(bedroom);
+        let _ = house.add(kitchen);
+        let _ = house.add(garage);
+        let _ = house.add(attic);
+
+        let names: Vec<&str> = house.rooms_by_power().iter().map(|r| r.name()).collect();
+
+        assert_eq!(names, vec!["Kitchen", "Garage", "Attic", "Bedroom"]);
+    }
+
+    #[test]
+    fn reject_blank_room_names() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+
+        for name in ["", "   ", "\t\n"] {
+            assert_eq!(
+                house.add(SmartRoom::new(name.to_string())),
+                Err(SmartHouseError::InvalidName(name.to_string()))
+            );
+        }
+
+        assert!(house.get_rooms().is_empty());
+    }
+
+    #[test]
+    fn reject_blank_device_names() {
+        let mut room = SmartRoom::new("Boiler".to_string());
+
+        for name in ["", "  "] {
+            assert_eq!(
+                room.plug(Arc::new(SmartSocket::new(name.to_string()))),
+                Err(SmartRoomError::InvalidName(name.to_string()))
+            );
+        }
+
+        assert!(room.devices().is_empty());
+    }
+
+    #[test]
+    fn search_devices() {
+        let mut living_room = SmartRoom::new("Living room".to_string());
+
+        let _ = living_room.plug(Arc::new(SmartSocket::new("Table Lamp".to_string())));
+        let _ = living_room.plug(Arc::new(SmartSocket::new("TV".to_string())));
+        let _ = living_room.plug(Arc::new(SmartSocket::new("Floor Lamp".to_string())));
+
+        let found: Vec<String> = living_room
+            .search_devices("lamp")
+            .iter()
+            .map(|d| d.name().to_string())
+            .collect();
+
+        assert_eq!(found, vec!["Table Lamp", "Floor Lamp"]);
+        assert!(living_room.search_devices("radio").is_empty());
+    }
+
+    #[test]
+    fn device_metadata() {
+        let mut socket = SmartSocket::new("Main socket".to_string());
+        socket.set_metadata("vendor".to_string(), "Acme".to_string());
+
+        let device: Arc<dyn Pluggable> = Arc::new(socket);
+        assert_eq!(
+            device.metadata().get("vendor").map(String::as_str),
+            Some("Acme")
+        );
+
+        let thermo = SmartThermometer::new("Main thermometer".to_string());
+        assert!(thermo.metadata().is_empty());
+    }
+
+    #[test]
+    fn plug_shared_device() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+
+        let _ = house.add(kitchen);
+        let _ = house.add(SmartRoom::new("Office".to_string()));
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+
+        let printer: Arc<dyn Pluggable> = Arc::new(SmartSocket::new("Printer".to_string()));
+
+        assert_eq!(
+            house.plug_shared(&["Office", "Attic"], printer.clone()),
+            Err(SmartHouseError::RoomNotFound("Attic".to_string()))
+        );
+        assert_eq!(house.shared_count(&printer), 0, "Failed call plugs nothing");
+
+        assert!(house
+            .plug_shared(&["Office", "Hall"], printer.clone())
+            .is_ok());
+        assert_eq!(house.shared_count(&printer), 2);
+
+        let found = house.find_all_devices("Printer");
+        assert_eq!(found.len(), 2);
+        assert!(Arc::ptr_eq(&found[0].1, &found[1].1));
+        assert!(Arc::ptr_eq(&found[0].1, &printer));
+
+        let report = house.create_report(DeviceListReport).unwrap();
+        let expected = "-> House: Sweet home
+Toaster (Kitchen)
+Printer (shared in: [Office, Hall])
+";
+
+        assert_eq!(report, expected, "Compare got and expected reports");
+    }
+
+    #[test]
+    fn devices_detailed() {
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+
+        let heater = SmartSocket::with_power("Heater".to_string(), 1500.0);
+        heater.turn_on();
+
+        let _ = kitchen.plug(Arc::new(heater));
+        let _ = kitchen.plug(Arc::new(SmartThermometer::new("Sensor".to_string())));
+
+        let statuses = kitchen.devices_detailed();
+
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].contains("1500"), "Got {}", statuses[0]);
+        assert!(statuses[0].contains("on"), "Got {}", statuses[0]);
+        assert!(statuses[1].contains("Sensor"), "Got {}", statuses[1]);
+    }
+
+    #[test]
+    fn cached_report() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+
+        let counter = CountingReport::default();
+        let report = CachedReport::new(&counter);
+
+        let first = house.create_report(&report).unwrap();
+        let second = house.create_report(&report).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(counter.calls(), 1, "Unchanged house is not reported again");
+
+        let version = house.version();
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        assert!(house.version() > version);
+
+        let third = house.create_report(&report).unwrap();
+
+        assert_ne!(first, third);
+        assert_eq!(counter.calls(), 2, "Changed house is reported again");
+    }
+
+    #[test]
+    fn cached_report_tracks_house_and_device_state() {
+        let report = CachedReport::new(EnergyReport::new(PowerUnit::Watts));
+
+        let kettle = Arc::new(SmartSocket::with_power("Kettle".to_string(), 1500.0));
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = house.plug("Kitchen", kettle.clone());
+
+        let off = house.create_report(&report).unwrap();
+        assert!(off.contains("Total: 0 W"), "Got {}", off);
+
+        let version = house.version();
+        kettle.turn_on();
+        assert_eq!(house.version(), version);
+
+        let on = house.create_report(&report).unwrap();
+        assert!(on.contains("Total: 1500 W"), "Got {}", on);
+
+        let mut other = SmartHouse::new("Other home".to_string());
+        let _ = other.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = other.plug("Kitchen", kettle.clone());
+        assert_eq!(other.version(), house.version());
+
+        let other_report = other.create_report(&report).unwrap();
+        assert!(
+            other_report.starts_with("-> House: Other home"),
+            "Got {}",
+            other_report
+        );
+    }
+
+    #[test]
+    fn house_version() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut version = house.version();
+
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        assert!(house.version() > version, "add bumps the version");
+        version = house.version();
+
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        assert!(house.version() > version, "add bumps the version");
+        version = house.version();
+
+        if let Some(mut kitchen) = house.get_room_mut("Kitchen") {
+            let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        }
+        assert!(house.version() > version, "plug through guard bumps it");
+        version = house.version();
+
+        if let Some(kitchen) = house.get_room_mut("Kitchen") {
+            assert_eq!(kitchen.devices(), vec!["Kettle"]);
+        }
+        assert_eq!(house.version(), version, "read-only access keeps it");
+
+        let _ = house.del("Bedroom");
+        assert!(house.version() > version, "del bumps the version");
+        version = house.version();
+
+        let _ = house.del("Attic");
+        assert_eq!(house.version(), version, "missing room changes nothing");
+    }
+
+    #[test]
+    fn socket_schedule() {
+        let on_at = SystemTime::UNIX_EPOCH + Duration::from_secs(8 * 3600);
+        let off_at = on_at + Duration::from_secs(3600);
+
+        let mut lamp = SmartSocket::with_power("Lamp".to_string(), 60.0);
+        lamp.set_schedule(Schedule::new(on_at, off_at));
+
+        lamp.tick(on_at - Duration::from_secs(1));
+        assert!(!lamp.is_on(), "Before on-time the socket stays off");
+
+        lamp.tick(on_at + Duration::from_secs(1));
+        assert!(lamp.is_on(), "After on-time the socket is on");
+        assert_eq!(lamp.power_usage(), 60.0);
+
+        lamp.tick(off_at + Duration::from_secs(1));
+        assert!(!lamp.is_on(), "After off-time the socket is off");
+    }
+
+    #[test]
+    fn iter_rooms_mut() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = bedroom.plug(Arc::new(SmartThermometer::new("Sensor".to_string())));
+
+        let _ = house.add(kitchen);
+        let _ = house.add(bedroom);
+
+        let version = house.version();
+
+        for room in house.iter_rooms_mut() {
+            for device in room.devices() {
+                room.unplug(&device);
             }
         }
 
-        impl Named for Robot {
-            fn name(&self) -> &str {
-                &self.name
+        assert!(house.get_rooms().iter().all(|r| r.devices().is_empty()));
+        assert!(house.version() > version);
+    }
+
+    #[test]
+    fn temperature_units() {
+        let freezing = Temperature::from_fahrenheit(32.0);
+        assert!(freezing.as_celsius().abs() < 1e-9);
+
+        let body = Temperature::from_celsius(36.6);
+        assert!((body.as_fahrenheit() - 97.88).abs() < 1e-9);
+
+        let mut thermo = SmartThermometer::new("Main thermometer".to_string());
+        thermo.set_temperature(Temperature::from_fahrenheit(212.0));
+        assert!((thermo.temperature().as_celsius() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn house_all_off() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+
+        let kettle = Arc::new(SmartSocket::with_power("Kettle".to_string(), 2000.0));
+        let lamp = Arc::new(SmartSocket::with_power("Lamp".to_string(), 60.0));
+        kettle.turn_on();
+        lamp.turn_on();
+
+        let _ = kitchen.plug(kettle.clone());
+        let _ = kitchen.plug(Arc::new(SmartThermometer::new("Sensor".to_string())));
+        let _ = bedroom.plug(lamp.clone());
+
+        let _ = house.add(kitchen);
+        let _ = house.add(bedroom);
+
+        house.all_off();
+
+        assert!(!kettle.is_on());
+        assert!(!lamp.is_on());
+        assert!(house.get_rooms().iter().all(|r| r.power_usage() == 0.0));
+    }
+
+    #[test]
+    fn report_write_to() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = house.add(kitchen);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        TreeReport.write_to(&house, &mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            TreeReport.make(&house).unwrap()
+        );
+
+        let missing = OwningDeviceInfoProvider {
+            socket: SmartSocket::new("Kettle".to_string()),
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+        assert!(missing.write_to(&house, &mut buffer).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn room_deep_eq() {
+        let mut first = SmartRoom::new("Kitchen".to_string());
+        let _ = first.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = first.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+
+        let mut second = SmartRoom::new("Kitchen".to_string());
+        let _ = second.plug(Arc::new(SmartSocket::new("Fridge".to_string())));
+
+        assert!(first == second, "PartialEq compares names only");
+        assert!(!first.deep_eq(&second), "Devices differ");
+
+        let mut third = SmartRoom::new("Kitchen".to_string());
+        let _ = third.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = third.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+
+        assert!(first.deep_eq(&third), "Plug order does not matter");
+    }
+
+    #[test]
+    fn reorder_devices() {
+        let mut living_room = SmartRoom::new("Living room".to_string());
+
+        for name in ["TV", "Lamp", "Radio", "Fan"] {
+            let _ = living_room.plug(Arc::new(SmartSocket::new(name.to_string())));
+        }
+
+        living_room.sort_devices_by_name();
+        assert_eq!(living_room.devices(), vec!["Fan", "Lamp", "Radio", "TV"]);
+
+        assert!(living_room.move_device_to("TV", 1).is_ok());
+        assert_eq!(living_room.devices(), vec!["Fan", "TV", "Lamp", "Radio"]);
+
+        assert!(living_room.move_device_to("Fan", 100).is_ok());
+        assert_eq!(living_room.devices(), vec!["TV", "Lamp", "Radio", "Fan"]);
+
+        assert_eq!(
+            living_room.move_device_to("Heater", 0),
+            Err(SmartRoomError::DeviceNotFound("Heater".to_string()))
+        );
+    }
+
+    #[test]
+    fn report_combined() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = house.add(kitchen);
+
+        let report = CombinedReport::new(vec![Box::new(TreeReport), Box::new(DeviceListReport)]);
+
+        let expected = "Sweet home
+└── Kitchen
+    └── Toaster
+
+-> House: Sweet home
+Toaster (Kitchen)
+";
+
+        assert_eq!(house.create_report(report).unwrap(), expected);
+
+        let failing = CombinedReport::new(vec![
+            Box::new(TreeReport),
+            Box::new(OwningDeviceInfoProvider {
+                socket: SmartSocket::new("Kettle".to_string()),
+            }),
+        ]);
+
+        assert!(house.create_report(failing).is_err());
+    }
+
+    #[test]
+    fn room_names() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+
+        for name in ["Kitchen", "Bedroom", "Attic"] {
+            let _ = house.add(SmartRoom::new(name.to_string()));
+        }
+
+        assert_eq!(house.room_names(), vec!["Kitchen", "Bedroom", "Attic"]);
+    }
+
+    #[test]
+    fn delete_room() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = house.add(kitchen);
+
+        match house.del("Kitchn") {
+            Err(e) => assert_eq!(e, SmartHouseError::RoomNotFound("Kitchn".to_string())),
+            Ok(_) => panic!("Misspelled room should not be removed"),
+        }
+        assert_eq!(house.room_names(), vec!["Kitchen"]);
+
+        let removed = house.del("Kitchen").unwrap();
+        assert_eq!(removed.devices(), vec!["Kettle"]);
+        assert!(house.get_rooms().is_empty());
+    }
+
+    #[test]
+    fn report_html() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartThermometer::new("Sensor <1>".to_string())));
+        let _ = house.add(kitchen);
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+
+        let report = house.create_report(HtmlReport).unwrap();
+        let expected = "<html>
+<body>
+<h1>Sweet home</h1>
+<h2>Kitchen</h2>
+<ul>
+<li>Sensor &lt;1&gt;</li>
+</ul>
+<h2>Hall</h2>
+</body>
+</html>
+";
+
+        assert_eq!(report, expected, "Compare got and expected reports");
+        assert!(!report.contains("<1>"));
+    }
+
+    #[test]
+    fn house_subscription() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Office".to_string()));
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+
+        let events: Rc<RefCell<Vec<(String, String, ChangeKind)>>> = Rc::default();
+        let sink = Rc::clone(&events);
+
+        house.subscribe(Box::new(move |room, device, kind| {
+            sink.borrow_mut()
+                .push((room.to_string(), device.to_string(), kind));
+        }));
+
+        let printer: Arc<dyn Pluggable> = Arc::new(SmartSocket::new("Printer".to_string()));
+        let _ = house.plug_shared(&["Office"], printer);
+
+        if let Some(mut hall) = house.get_room_mut("Hall") {
+            let _ = hall.plug(Arc::new(SmartSocket::new("Lamp".to_string())));
+            hall.unplug("Lamp");
+        }
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                (
+                    "Office".to_string(),
+                    "Printer".to_string(),
+                    ChangeKind::Plugged
+                ),
+                ("Hall".to_string(), "Lamp".to_string(), ChangeKind::Plugged),
+                (
+                    "Hall".to_string(),
+                    "Lamp".to_string(),
+                    ChangeKind::Unplugged
+                ),
+            ]
+        );
+
+        // Удаленная из дома комната больше не сообщает о своих изменениях.
+        let mut office = house.del("Office").unwrap();
+        office.unplug("Printer");
+        assert_eq!(events.borrow().len(), 3);
+    }
+
+    #[test]
+    fn socket_power_history() {
+        let mut heater = SmartSocket::with_power("Heater".to_string(), 1000.0);
+        let total = SmartSocket::HISTORY_CAPACITY + 5;
+
+        for i in 0..total {
+            match i % 2 {
+                0 => heater.turn_on(),
+                _ => heater.turn_off(),
             }
+
+            heater.record_sample(SystemTime::UNIX_EPOCH + Duration::from_secs(i as u64));
         }
 
-        impl Pluggable for Robot {}
+        let history = heater.history();
+        assert_eq!(history.len(), SmartSocket::HISTORY_CAPACITY);
+
+        let timestamps: Vec<SystemTime> = history.iter().map(|(t, _)| *t).collect();
+        let expected: Vec<SystemTime> = (5..total)
+            .map(|i| SystemTime::UNIX_EPOCH + Duration::from_secs(i as u64))
+            .collect();
+        assert_eq!(
+            timestamps, expected,
+            "Only the most recent samples are kept"
+        );
+
+        assert_eq!(history[0].1, 0.0);
+        assert_eq!(history[1].1, 1000.0);
+    }
+
+    #[test]
+    fn house_try_from_rooms() {
+        let rooms = vec![
+            SmartRoom::new("Kitchen".to_string()),
+            SmartRoom::new("Bedroom".to_string()),
+            SmartRoom::new("Hall".to_string()),
+        ];
+
+        match SmartHouse::try_from(("Sweet home".to_string(), rooms)) {
+            Ok(house) => {
+                assert_eq!(house.name(), "Sweet home");
+                assert_eq!(house.room_names(), vec!["Kitchen", "Bedroom", "Hall"]);
+            }
+            Err(e) => panic!("Distinct rooms should build a house: {e}"),
+        }
+
+        let rooms = vec![
+            SmartRoom::new("Kitchen".to_string()),
+            SmartRoom::new("Hall".to_string()),
+            SmartRoom::new("Kitchen".to_string()),
+        ];
+
+        match SmartHouse::try_from(("Sweet home".to_string(), rooms)) {
+            Err(e) => assert_eq!(e, SmartHouseError::RoomAlreadyExists("Kitchen".to_string())),
+            Ok(_) => panic!("Duplicate rooms should be rejected"),
+        }
+    }
+
+    #[test]
+    fn plug_null_device() {
+        let mut lab = SmartRoom::new("Lab".to_string());
+        let probe = Arc::new(NullDevice::new("Probe".to_string()));
+
+        assert!(lab.plug(probe.clone()).is_ok());
+        assert!(lab.is_connected(&*probe));
+        assert_eq!(lab.devices(), vec!["Probe"]);
+        assert_eq!(lab.power_usage(), 0.0);
+        assert!(probe.metadata().is_empty());
+        assert!(!probe.is_on());
+    }
+
+    #[test]
+    fn report_filtered() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        let mut guest_bedroom = SmartRoom::new("Bedroom 2".to_string());
+
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = bedroom.plug(Arc::new(SmartSocket::new("Lamp".to_string())));
+        let _ = guest_bedroom.plug(Arc::new(SmartSocket::new("Fan".to_string())));
+
+        let _ = house.add(kitchen);
+        let _ = house.add(bedroom);
+        let _ = house.add(guest_bedroom);
+
+        let report = FilteredReport::new(DeviceListReport, |name: &str| name.starts_with("Bed"));
+        let report = house.create_report(report).unwrap();
+
+        let expected = "-> House: Sweet home
+Lamp (Bedroom)
+Fan (Bedroom 2)
+";
+
+        assert_eq!(report, expected, "Compare got and expected reports");
+        assert!(!report.contains("Kitchen"));
+        assert_eq!(house.room_names().len(), 3, "Source house is untouched");
+    }
+
+    #[test]
+    fn socket_health() {
+        let mut heater = SmartSocket::with_power("Heater".to_string(), 3000.0);
+        heater.turn_on();
+        assert_eq!(heater.health(), DeviceHealth::Ok, "No limit configured");
+
+        heater.set_power_limit(2000.0);
+
+        match heater.health() {
+            DeviceHealth::Warning(reason) => assert!(reason.contains("3000")),
+            other => panic!("Expected a warning, got {other:?}"),
+        }
+
+        heater.turn_off();
+        assert_eq!(heater.health(), DeviceHealth::Ok);
+
+        let thermo = SmartThermometer::new("Main thermometer".to_string());
+        assert_eq!(thermo.health(), DeviceHealth::Ok);
+    }
+
+    #[test]
+    fn report_health() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = bedroom.plug(Arc::new(SmartSocket::new("Lamp".to_string())));
+        let _ = house.add(kitchen);
+        let _ = house.add(bedroom);
+
+        let report = house.create_report(HealthReport).unwrap();
+        assert_eq!(report, "-> House: Sweet home\nAll systems nominal\n");
+
+        if let Some(mut kitchen) = house.get_room_mut("Kitchen") {
+            let _ = kitchen.plug(Arc::new(Broken::new("Kettle".to_string())));
+        }
+
+        let report = house.create_report(HealthReport).unwrap();
+        let expected = "-> House: Sweet home
+--> Room: Kitchen
+Kettle: fault: heating element broken
+";
+
+        assert_eq!(report, expected, "Compare got and expected reports");
+    }
+
+    #[test]
+    fn swap_rooms() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        let _ = house.add(SmartRoom::new("Garage".to_string()));
+
+        assert!(house.swap_rooms("Kitchen", "Garage").is_ok());
+
+        let names: Vec<&str> = house.get_rooms().iter().map(|r| r.name()).collect();
+        assert_eq!(names, vec!["Garage", "Bedroom", "Kitchen"]);
+
+        assert!(
+            matches!(
+                house.swap_rooms("Kitchen", "Attic"),
+                Err(SmartHouseError::RoomNotFound(name)) if name == "Attic"
+            ),
+            "Swapping with a missing room fails"
+        );
+    }
+
+    #[test]
+    fn create_report_with_reuses_report() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+
+        let report: Box<dyn Reportable> = Box::new(CountingReport::default());
+
+        let first = house.create_report_with(report.as_ref()).unwrap();
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        let second = house.create_report_with(report.as_ref()).unwrap();
+
+        assert_eq!(first, "1 rooms");
+        assert_eq!(second, "2 rooms");
+    }
+
+    #[test]
+    fn room_contains_by_name() {
+        let mut room = SmartRoom::new("Living room".to_string());
+        assert!(!room.contains("TV"));
+
+        let _ = room.plug(Arc::new(SmartSocket::new("TV".to_string())));
+
+        assert!(room.contains("TV"));
+        assert!(!room.contains("tv"), "Lookup is case sensitive");
+    }
+
+    #[test]
+    fn report_timed() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+
+        let report = house
+            .create_report(TimedReport::new(CountingReport::default()))
+            .unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("generated at "));
+        assert!(lines[0].ends_with(" UTC"));
+        assert_eq!(lines[1], "1 rooms");
+        assert!(lines[2].starts_with("generated in "));
+        assert!(lines[2].ends_with(" ms"));
+    }
+
+    #[test]
+    fn validate_house() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Lamp".to_string())));
+        let _ = bedroom.plug(Arc::new(SmartSocket::new("Lamp".to_string())));
+        let _ = house.add(kitchen);
+        let _ = house.add(bedroom);
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+
+        let issues = house.validate();
+
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::EmptyRoom("Hall".to_string()),
+                ValidationIssue::DuplicateDeviceName {
+                    device: "Lamp".to_string(),
+                    rooms: vec!["Kitchen".to_string(), "Bedroom".to_string()],
+                },
+            ]
+        );
+        assert_eq!(issues[0].to_string(), "room Hall has no devices");
+    }
+
+    #[test]
+    fn room_deep_clone() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+        let _ = room.plug(Arc::new(SmartSocket::with_power(
+            "Toaster".to_string(),
+            800.0,
+        )));
+
+        let shallow = room.clone();
+        let deep = room.deep_clone();
+
+        for device in deep.search_devices("Toaster") {
+            device.set_power(true);
+        }
+
+        assert_eq!(deep.power_usage(), 800.0);
+        assert_eq!(room.power_usage(), 0.0, "Original is not affected");
+        assert_eq!(shallow.power_usage(), 0.0);
+        assert!(deep.deep_eq(&room));
+    }
+
+    #[test]
+    fn room_category() {
+        let room = SmartRoom::new("Hall".to_string());
+        assert_eq!(room.category(), RoomCategory::Other);
+
+        let room = SmartRoom::with_category("Kitchen".to_string(), RoomCategory::Kitchen);
+        assert_eq!(room.category(), RoomCategory::Kitchen);
+        assert_eq!(room.clone().category(), RoomCategory::Kitchen);
+    }
+
+    #[test]
+    fn rooms_by_category() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::with_category(
+            "Master bedroom".to_string(),
+            RoomCategory::Bedroom,
+        ));
+        let _ = house.add(SmartRoom::with_category(
+            "Kitchen".to_string(),
+            RoomCategory::Kitchen,
+        ));
+        let _ = house.add(SmartRoom::with_category(
+            "Guest bedroom".to_string(),
+            RoomCategory::Bedroom,
+        ));
+
+        let groups = house.rooms_by_category();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&RoomCategory::Bedroom].len(), 2);
+        assert_eq!(groups[&RoomCategory::Kitchen].len(), 1);
+        assert_eq!(groups[&RoomCategory::Bedroom][1].name(), "Guest bedroom");
+    }
+
+    #[test]
+    fn describe_device() {
+        let socket = SmartSocket::with_power("Printer".to_string(), 1500.0);
+        socket.turn_on();
+
+        let device: Arc<dyn Pluggable> = Arc::new(socket);
+        assert_eq!(format!("{}", device), "socket Printer (on, 1500W)");
+
+        device.set_power(false);
+        assert_eq!(device.describe(), "socket Printer (off, 1500W)");
+
+        let robot: Arc<dyn Pluggable> = Arc::new(Robot::new("Vacuum".to_string()));
+        assert_eq!(robot.to_string(), "Vacuum");
+    }
+
+    #[test]
+    fn active_count() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut room = SmartRoom::new("Kitchen".to_string());
+
+        let kettle = Arc::new(SmartSocket::new("Kettle".to_string()));
+        let toaster = Arc::new(SmartSocket::new("Toaster".to_string()));
+        let _ = room.plug(kettle.clone());
+        let _ = room.plug(toaster.clone());
+        let _ = room.plug(Arc::new(SmartSocket::new("Mixer".to_string())));
+
+        kettle.turn_on();
+        toaster.turn_on();
+        assert_eq!(room.active_count(), 2);
+
+        let _ = house.add(room);
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        let lamp: Arc<dyn Pluggable> = Arc::new(SmartSocket::new("Lamp".to_string()));
+        lamp.set_power(true);
+        let _ = house.plug_shared(&["Kitchen", "Bedroom"], lamp);
+
+        assert_eq!(house.active_count(), 3, "Shared lamp is counted once");
+    }
+
+    #[test]
+    fn socket_builder() {
+        let socket = SmartSocketBuilder::new("Heater".to_string())
+            .power(2000.0)
+            .initially_on(true)
+            .build();
+
+        assert!(socket.is_on());
+        assert_eq!(socket.power_usage(), 2000.0);
+
+        let socket = SmartSocket::builder("Lamp".to_string()).power(60.0).build();
+        assert!(!socket.is_on());
+        assert_eq!(socket.status(), "Socket[Lamp]: off, 60W");
+    }
+
+    #[test]
+    fn example_house_cli() {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new(env!("CARGO"))
+            .args(["run", "--quiet", "--example", "house_cli"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to start house_cli example");
+
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(b"add-room Kitchen\nplug Kitchen Kettle 2000\nreport\n")
+            .unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        let expected = "room Kitchen added
+device Kettle plugged into Kitchen
+CLI house
+└── Kitchen
+    └── Kettle
+";
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    }
+
+    #[test]
+    fn report_dot() {
+        let mut house = SmartHouse::new("Sweet \"home\"".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = house.add(kitchen);
+
+        let report = house.create_report(DotReport).unwrap();
+        let expected = r#"digraph house {
+    house [label="Sweet \"home\""];
+    room0 [label="Kitchen"];
+    house -> room0;
+    device0 [label="Kettle"];
+    room0 -> device0;
+}
+"#;
+
+        assert!(report.starts_with("digraph"));
+        assert!(report.contains("house -> room0;"));
+        assert_eq!(report, expected, "Compare got and expected reports");
+    }
+
+    #[test]
+    fn over_power_devices() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+
+        let mixer = SmartSocket::with_power("Mixer".to_string(), 500.0);
+        let kettle = SmartSocket::with_power("Kettle".to_string(), 2000.0);
+        mixer.turn_on();
+        kettle.turn_on();
+
+        let _ = room.plug(Arc::new(mixer));
+        let _ = room.plug(Arc::new(kettle));
+
+        let found = room.over_power_devices(1000.0);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "Kettle");
+    }
+
+    #[test]
+    fn export_env() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster #1".to_string())));
+        let _ = house.add(kitchen);
+
+        let env = house.export_env();
+        let keys: Vec<&str> = env.lines().filter_map(|l| l.split('=').next()).collect();
+
+        assert_eq!(keys, vec!["HOUSE_NAME", "ROOM_1", "ROOM_1_DEVICE_1"]);
+        assert_eq!(
+            env,
+            "HOUSE_NAME=Sweet_home\nROOM_1=Kitchen\nROOM_1_DEVICE_1=Toaster__1\n"
+        );
+    }
+
+    #[test]
+    fn plug_or_replace() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+
+        let old: Arc<dyn Pluggable> =
+            Arc::new(SmartSocket::with_power("Kettle".to_string(), 1500.0));
+        let new: Arc<dyn Pluggable> =
+            Arc::new(SmartSocket::with_power("Kettle".to_string(), 2200.0));
+        let _ = room.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+
+        assert!(room.plug_or_replace(old.clone()).unwrap().is_none());
+
+        let replaced = room
+            .plug_or_replace(new.clone())
+            .unwrap()
+            .expect("Kettle is replaced");
+        assert!(Arc::ptr_eq(&replaced, &old));
+
+        new.set_power(true);
+        assert_eq!(room.devices(), vec!["Toaster", "Kettle"]);
+        assert_eq!(room.power_usage(), 2200.0);
+
+        let blank: Arc<dyn Pluggable> = Arc::new(SmartSocket::new("  ".to_string()));
+        assert_eq!(
+            room.plug_or_replace(blank).err(),
+            Some(SmartRoomError::InvalidName("  ".to_string()))
+        );
+        assert_eq!(room.devices(), vec!["Toaster", "Kettle"]);
+    }
+
+    #[test]
+    fn plug_or_replace_checks_power_alert() {
+        let alerts = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&alerts);
+        let mut room = SmartRoom::new("Kitchen".to_string());
+        room.set_power_alert(2000.0, Box::new(move |total| sink.borrow_mut().push(total)));
+
+        let small = SmartSocket::with_power("Kettle".to_string(), 1500.0);
+        small.turn_on();
+        room.plug_or_replace(Arc::new(small)).unwrap();
+        assert!(alerts.borrow().is_empty());
+
+        let big = SmartSocket::with_power("Kettle".to_string(), 2200.0);
+        big.turn_on();
+        room.plug_or_replace(Arc::new(big)).unwrap();
+        assert_eq!(*alerts.borrow(), vec![2200.0]);
+    }
+
+    #[test]
+    fn thermometer_last_seen() {
+        let mut thermometer = SmartThermometer::new("Outdoor".to_string());
+        assert_eq!(thermometer.last_seen(), None);
+
+        let start = SystemTime::now();
+        thermometer.update(Temperature::from_celsius(12.0), start);
+        assert_eq!(thermometer.last_seen(), Some(start));
+
+        let later = start + Duration::from_secs(30);
+        thermometer.update(Temperature::from_celsius(12.5), later);
+        assert_eq!(thermometer.last_seen(), Some(later));
+        assert_eq!(thermometer.temperature().as_celsius(), 12.5);
+
+        assert_eq!(SmartSocket::new("Lamp".to_string()).last_seen(), None);
+    }
+
+    #[test]
+    fn house_snapshot() {
+        fn assert_send_sync<T: Clone + Send + Sync>(_: &T) {}
+
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = house.add(kitchen);
+
+        let snapshot = house.snapshot();
+        assert_send_sync(&snapshot);
+
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        if let Some(mut kitchen) = house.get_room_mut("Kitchen") {
+            kitchen.unplug("Toaster");
+        }
+        house.set_name("New home".to_string());
+
+        assert_eq!(
+            snapshot,
+            HouseSnapshot {
+                name: "Sweet home".to_string(),
+                rooms: vec![RoomSnapshot {
+                    name: "Kitchen".to_string(),
+                    devices: vec!["Toaster".to_string()],
+                    subrooms: vec![],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn report_formats() {
+        let mut house = SmartHouse::new("Sweet \"home\"".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster, big".to_string())));
+        let _ = house.add(kitchen);
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+
+        let json = make_report(ReportFormat::Json).make(&house).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"Sweet \"home\"","rooms":[{"name":"Kitchen","devices":["Kettle","Toaster, big"]},{"name":"Hall","devices":[]}]}
+"#
+        );
+
+        let csv = make_report(ReportFormat::Csv).make(&house).unwrap();
+        assert_eq!(
+            csv,
+            "room,device\nKitchen,Kettle\nKitchen,\"Toaster, big\"\n"
+        );
+
+        let markdown = make_report(ReportFormat::Markdown).make(&house).unwrap();
+        assert_eq!(
+            markdown,
+            "# Sweet \"home\"\n\n## Kitchen\n\n- Kettle\n- Toaster, big\n\n## Hall\n"
+        );
+
+        let text = make_report(ReportFormat::Text).make(&house).unwrap();
+        assert_eq!(text, house.create_report(TreeReport).unwrap());
+    }
+
+    #[test]
+    fn device_names_sorted() {
+        let names = ["TV", "Lamp", "Speaker", "Air conditioner"];
+
+        let mut forward = SmartRoom::new("Living room".to_string());
+        let mut backward = SmartRoom::new("Living room".to_string());
+
+        for name in names.iter() {
+            let _ = forward.plug(Arc::new(SmartSocket::new(name.to_string())));
+        }
+        for name in names.iter().rev() {
+            let _ = backward.plug(Arc::new(SmartSocket::new(name.to_string())));
+        }
+
+        let expected = vec!["Air conditioner", "Lamp", "Speaker", "TV"];
+        assert_eq!(forward.device_names_sorted(), expected);
+        assert_eq!(backward.device_names_sorted(), expected);
+    }
+
+    #[test]
+    fn report_energy_units() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+
+        let kettle = SmartSocket::with_power("Kettle".to_string(), 1500.0);
+        let toaster = SmartSocket::with_power("Toaster".to_string(), 800.0);
+        kettle.turn_on();
+        toaster.turn_on();
+        let _ = kitchen.plug(Arc::new(kettle));
+        let _ = kitchen.plug(Arc::new(toaster));
+        let _ = house.add(kitchen);
+
+        let watts = house
+            .create_report(EnergyReport::new(PowerUnit::Watts))
+            .unwrap();
+        let kilowatts = house
+            .create_report(EnergyReport::new(PowerUnit::Kilowatts))
+            .unwrap();
+
+        assert_eq!(
+            watts,
+            "-> House: Sweet home\n--> Room: Kitchen\nKettle: 1500 W\nToaster: 800 W\nTotal: 2300 W\n"
+        );
+        assert_eq!(
+            kilowatts,
+            "-> House: Sweet home\n--> Room: Kitchen\nKettle: 1.5 kW\nToaster: 0.8 kW\nTotal: 2.3 kW\n"
+        );
+    }
+
+    #[test]
+    fn house_plug_into_room() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let version = house.version();
+
+        assert!(house
+            .plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string())))
+            .is_ok());
+        assert_eq!(house.get_rooms()[0].devices(), vec!["Kettle"]);
+        assert!(house.version() > version);
+
+        assert_eq!(
+            house.plug("Attic", Arc::new(SmartSocket::new("Lamp".to_string()))),
+            Err(SmartHouseError::RoomNotFound("Attic".to_string()))
+        );
+        assert_eq!(
+            house.plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string()))),
+            Err(SmartHouseError::Room(SmartRoomError::DeviceAlreadyPlugged(
+                "Kettle".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn thermometer_as_sensor() {
+        let mut thermometer = SmartThermometer::new("Outdoor".to_string());
+        thermometer.set_temperature(Temperature::from_fahrenheit(50.0));
+
+        let sensor: &dyn Sensor = &thermometer;
+
+        assert_eq!(sensor.name(), "Outdoor");
+        assert_eq!(sensor.read(), 10.0);
+        assert_eq!(sensor.unit(), "°C");
+    }
+
+    #[test]
+    fn room_devices_arc() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+
+        for (name, power) in [("Kettle", 1500.0), ("Toaster", 800.0), ("Fridge", 150.0)] {
+            let socket = SmartSocket::with_power(name.to_string(), power);
+            socket.turn_on();
+            let _ = room.plug(Arc::new(socket));
+        }
+
+        let total: f64 = room.devices_arc().iter().map(|d| d.power_usage()).sum();
+
+        assert_eq!(room.devices_arc().len(), 3);
+        assert_eq!(total, 2450.0);
+        assert_eq!(total, room.power_usage());
+    }
+
+    #[test]
+    fn try_total_power() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut garage = SmartRoom::new("Garage".to_string());
+        let _ = garage.plug(Arc::new(Heater::new("Heater".to_string(), 1200.0)));
+        let _ = house.add(garage);
+
+        assert_eq!(house.try_total_power(), Ok(1200.0));
+
+        let _ = house.plug(
+            "Garage",
+            Arc::new(Heater::new("Welder".to_string(), f64::NAN)),
+        );
+
+        match house.try_total_power() {
+            Err(PowerError::InvalidReading { device, value }) => {
+                assert_eq!(device, "Welder");
+                assert!(value.is_nan());
+            }
+            other => panic!("Expected invalid reading, got {:?}", other),
+        }
+
+        let _ = house.del("Garage");
+        let mut hall = SmartRoom::new("Hall".to_string());
+        let _ = hall.plug(Arc::new(Heater::new("A".to_string(), f64::MAX)));
+        let _ = hall.plug(Arc::new(Heater::new("B".to_string(), f64::MAX)));
+        let _ = house.add(hall);
+
+        assert_eq!(house.try_total_power(), Err(PowerError::Overflow));
+    }
+
+    #[test]
+    fn stream_report_large_house() {
+        let mut house = SmartHouse::new("Big house".to_string());
+
+        for r in 0..10 {
+            let mut room = SmartRoom::new(format!("Room {}", r));
+            for d in 0..100 {
+                let _ = room.plug(Arc::new(SmartSocket::new(format!("Socket {}-{}", r, d))));
+            }
+            let _ = house.add(room);
+        }
+
+        for format in [
+            ReportFormat::Text,
+            ReportFormat::Json,
+            ReportFormat::Csv,
+            ReportFormat::Markdown,
+        ] {
+            let mut out: Vec<u8> = Vec::new();
+            house.stream_report(&mut out, format).unwrap();
+
+            let made = make_report(format).make(&house).unwrap();
+            assert_eq!(out.len(), made.len(), "{:?}", format);
+            assert_eq!(String::from_utf8(out).unwrap(), made);
+        }
+    }
+
+    #[test]
+    fn room_power_alert() {
+        let alerts: Rc<RefCell<Vec<f64>>> = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&alerts);
+
+        let mut room = SmartRoom::new("Kitchen".to_string());
+        room.set_power_alert(2000.0, Box::new(move |total| sink.borrow_mut().push(total)));
+
+        let _ = room.plug(Arc::new(Heater::new("Fridge".to_string(), 300.0)));
+        let _ = room.plug(Arc::new(Heater::new("Oven".to_string(), 1500.0)));
+        assert!(alerts.borrow().is_empty(), "Below the threshold");
+
+        let _ = room.plug(Arc::new(Heater::new("Kettle".to_string(), 1200.0)));
+        assert_eq!(*alerts.borrow(), vec![3000.0]);
+    }
+
+    #[test]
+    fn ensure_room() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+
+        assert!(house.ensure_room(kitchen));
+        assert!(!house.ensure_room(SmartRoom::new("Kitchen".to_string())));
+        assert!(!house.ensure_room(SmartRoom::new("  ".to_string())));
+
+        assert_eq!(house.get_rooms().len(), 1);
+        assert_eq!(
+            house.get_rooms()[0].devices(),
+            vec!["Kettle"],
+            "Existing room is kept"
+        );
+    }
+
+    #[test]
+    fn map_rooms_with_prefix() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = house.add(kitchen);
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+
+        let house = house
+            .map_rooms(|room| {
+                SmartRoom::try_from_devices(
+                    format!("1F {}", room.name()),
+                    room.devices_arc().to_vec(),
+                )
+                .unwrap()
+            })
+            .unwrap();
+
+        assert_eq!(house.name(), "Sweet home");
+        assert_eq!(house.room_names(), vec!["1F Kitchen", "1F Bedroom"]);
+        assert_eq!(house.get_rooms()[0].devices(), vec!["Kettle"]);
+    }
+
+    #[test]
+    fn report_diff() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = house.add(kitchen);
+
+        let report = DiffReport {
+            baseline: house.clone(),
+        };
+        assert_eq!(house.create_report(&report).unwrap(), "No changes\n");
+
+        let _ = house.plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = house.add(SmartRoom::new("Garage".to_string()));
+
+        let expected = "+ rooms
+Garage
++ devices
+Kitchen: Kettle
+";
+
+        assert_eq!(house.create_report(&report).unwrap(), expected);
+    }
+
+    #[test]
+    fn house_as_graph() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut office = SmartRoom::new("Office".to_string());
+        let _ = office.plug(Arc::new(SmartSocket::new("Lamp".to_string())));
+        let _ = house.add(office);
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+        let _ = house.add(SmartRoom::new("Garage".to_string()));
+
+        let printer: Arc<dyn Pluggable> = Arc::new(SmartSocket::new("Printer".to_string()));
+        let _ = house.plug_shared(&["Office", "Hall"], printer);
+
+        let graph: HouseGraph = house.as_graph();
+
+        assert_eq!(graph.rooms(), vec!["Office", "Hall", "Garage"]);
+        assert_eq!(
+            graph.devices_in("Office"),
+            Some(&["Lamp".to_string(), "Printer".to_string()][..])
+        );
+        assert_eq!(graph.devices_in("Attic"), None);
+        assert_eq!(graph.neighbors("Office"), vec!["Hall"]);
+        assert_eq!(graph.neighbors("Hall"), vec!["Office"]);
+        assert!(graph.neighbors("Garage").is_empty());
+    }
+
+    #[test]
+    fn socket_firmware_upgrade() {
+        let mut socket = SmartSocket::new("Heater".to_string());
+        assert_eq!(socket.firmware(), "unknown");
+
+        socket.upgrade_firmware("2.0.1".to_string());
+        assert_eq!(socket.firmware(), "2.0.1");
+
+        let device: Arc<dyn Pluggable> = Arc::new(socket);
+        assert_eq!(device.firmware(), "2.0.1");
+        assert_eq!(Robot::new("Vacuum".to_string()).firmware(), "unknown");
+    }
+
+    #[test]
+    fn filter_rooms_non_empty() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = house.add(kitchen);
+        let _ = house.add(SmartRoom::new("Hall".to_string()));
+        let _ = house.add(SmartRoom::new("Garage".to_string()));
+
+        let filtered = house.filter_rooms(|room| !room.devices().is_empty());
+
+        assert_eq!(filtered.name(), "Sweet home");
+        assert_eq!(filtered.get_rooms().len(), 1);
+        assert_eq!(filtered.room_names(), vec!["Kitchen"]);
+        assert_eq!(house.get_rooms().len(), 3, "Source house is untouched");
+    }
+
+    #[test]
+    fn parse_text_report_round_trip() {
+        let mut house = SmartHouse::new("Millennium Falcon".to_string());
+        let mut wheelhouse = SmartRoom::new("Wheelhouse".to_string());
+
+        let thermo = SmartThermometer::new("Main thermometer".to_string());
+        let socket = SmartSocket::new("Main socket".to_string());
+
+        let _ = wheelhouse.plug(Arc::new(thermo.clone()));
+        let _ = wheelhouse.plug(Arc::new(socket.clone()));
+        let _ = house.add(wheelhouse);
+
+        let report = house
+            .create_report(BorrowingDeviceInfoProvider {
+                socket: &socket,
+                thermo: &thermo,
+            })
+            .unwrap();
+
+        assert_eq!(
+            parse_text_report(&report),
+            Ok(vec![(
+                "Wheelhouse".to_string(),
+                vec!["Main socket".to_string(), "Main thermometer".to_string()]
+            )])
+        );
+
+        assert_eq!(
+            parse_text_report("-> House: Home\n----> Device: Socket[Lamp]\n"),
+            Err(ParseError::DeviceOutsideRoom(2))
+        );
+        assert!(matches!(
+            parse_text_report("--> Room: Hall\nnot found\n"),
+            Err(ParseError::MalformedLine { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn plug_at_index() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+        let _ = room.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = room.plug(Arc::new(SmartSocket::new("Mixer".to_string())));
+
+        assert!(room
+            .plug_at(0, Arc::new(SmartSocket::new("Kettle".to_string())))
+            .is_ok());
+        assert!(room
+            .plug_at(100, Arc::new(SmartSocket::new("Fridge".to_string())))
+            .is_ok());
+
+        assert_eq!(room.devices(), vec!["Kettle", "Toaster", "Mixer", "Fridge"]);
+        assert_eq!(
+            room.plug_at(1, Arc::new(SmartSocket::new("Mixer".to_string()))),
+            Err(SmartRoomError::DeviceAlreadyPlugged("Mixer".to_string()))
+        );
+    }
+
+    #[test]
+    fn device_energy_cost() {
+        let heater = Heater::new("Heater".to_string(), 1000.0);
+        assert!((heater.energy_cost(0.20, 2.0) - 0.40).abs() < 1e-9);
+
+        let socket = SmartSocket::with_power("Kettle".to_string(), 1000.0);
+        assert_eq!(socket.energy_cost(0.20, 2.0), 0.0, "Switched off socket");
+    }
+
+    #[test]
+    fn report_cost() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+
+        let kettle = SmartSocket::with_power("Kettle".to_string(), 1000.0);
+        let heater = SmartSocket::with_power("Heater".to_string(), 2500.0);
+        kettle.turn_on();
+        heater.turn_on();
+
+        let expected_total = kettle.energy_cost(0.20, 2.0) + heater.energy_cost(0.20, 2.0);
+
+        let _ = kitchen.plug(Arc::new(kettle));
+        let _ = kitchen.plug(Arc::new(heater));
+        let _ = house.add(kitchen);
+
+        let report = house.create_report(CostReport::new(0.20, 2.0)).unwrap();
+        let expected = "-> House: Sweet home
+--> Room: Kitchen
+Kettle: 0.40
+Heater: 1.00
+Total: 1.40
+";
+
+        assert_eq!(report, expected, "Compare got and expected reports");
+        assert!(report.ends_with(&format!("Total: {:.2}\n", expected_total)));
+    }
+
+    #[test]
+    fn take_room_to_other_house() {
+        let mut old_house = SmartHouse::new("Old home".to_string());
+        let mut new_house = SmartHouse::new("New home".to_string());
+
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = kitchen.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = old_house.add(kitchen);
+
+        let kitchen = old_house.take_room("Kitchen").expect("Kitchen exists");
+        assert!(old_house.get_rooms().is_empty());
+        assert!(old_house.take_room("Kitchen").is_none());
+
+        assert!(new_house.add(kitchen).is_ok());
+        assert_eq!(
+            new_house.get_rooms()[0].devices(),
+            vec!["Kettle", "Toaster"]
+        );
+    }
+
+    #[test]
+    fn rooms_keep_insertion_order() {
+        let names = ["Kitchen", "Attic", "Bedroom", "Zen room", "Basement"];
+        let mut house = SmartHouse::new("Sweet home".to_string());
+
+        for name in names.iter() {
+            let _ = house.add(SmartRoom::new(name.to_string()));
+        }
+
+        assert_eq!(house.room_names(), names.to_vec());
+
+        let _ = house.del("Attic");
+        let _ = house.add(SmartRoom::new("Attic".to_string()));
+
+        let order: Vec<&str> = house.get_rooms().iter().map(|r| r.name()).collect();
+        assert_eq!(
+            order,
+            vec!["Kitchen", "Bedroom", "Zen room", "Basement", "Attic"]
+        );
+    }
+
+    #[test]
+    fn socket_reset() {
+        let mut socket = SmartSocket::with_power("Heater".to_string(), 1500.0);
+        socket.turn_on();
+        socket.set_metadata("vendor".to_string(), "Acme".to_string());
+        socket.record_sample(SystemTime::now());
+
+        socket.reset();
+
+        assert_eq!(socket.name(), "Heater");
+        assert!(!socket.is_on());
+        assert!(socket.metadata().is_empty());
+        assert!(socket.history().is_empty());
+
+        let mut robot = Robot::new("Vacuum".to_string());
+        robot.reset();
+        assert_eq!(robot.name(), "Vacuum");
+    }
+
+    #[test]
+    fn all_devices_with_room() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+        let _ = house.plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = house.plug("Kitchen", Arc::new(SmartSocket::new("Toaster".to_string())));
+        let _ = house.plug("Bedroom", Arc::new(SmartSocket::new("Lamp".to_string())));
+
+        let pairs: Vec<(&str, &str)> = house
+            .all_devices_with_room()
+            .map(|(room, device)| (room, device.name()))
+            .collect();
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("Kitchen", "Kettle"),
+                ("Kitchen", "Toaster"),
+                ("Bedroom", "Lamp")
+            ]
+        );
+    }
+
+    #[test]
+    fn room_total_of() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+        let _ = room.plug(Arc::new(Heater::new("Oven".to_string(), 2000.0)));
+        let _ = room.plug(Arc::new(Heater::new("Kettle".to_string(), 1500.0)));
+        let _ = room.plug(Arc::new(Robot::new("Vacuum".to_string())));
+
+        assert_eq!(room.total_of(|d| d.power_usage()), 3500.0);
+        assert_eq!(room.total_of(|d| d.name().len() as f64), 16.0);
+        assert_eq!(room.total_of(|d| d.energy_cost(0.5, 2.0)), 3.5);
+    }
+
+    #[test]
+    fn room_index() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+
+        assert_eq!(house.room_index("Kitchen"), Some(0));
+        assert_eq!(house.room_index("Bedroom"), Some(1));
+        assert_eq!(house.room_index("Attic"), None);
+    }
+
+    #[test]
+    fn house_inventory() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = house.add(SmartRoom::new("Garage".to_string()));
+
+        let kettle = SmartSocket::with_power("Kettle".to_string(), 1500.0);
+        kettle.turn_on();
+        let _ = house.plug("Kitchen", Arc::new(kettle));
+        let _ = house.plug(
+            "Kitchen",
+            Arc::new(SmartThermometer::new("Sensor".to_string())),
+        );
+        let _ = house.plug("Garage", Arc::new(Robot::new("Vacuum".to_string())));
+
+        let inventory = house.inventory();
+
+        assert_eq!(inventory.len(), house.device_count());
+        assert_eq!(
+            inventory[0],
+            DeviceInventoryEntry {
+                room: "Kitchen".to_string(),
+                name: "Kettle".to_string(),
+                kind: "socket",
+                power: 1500.0,
+            }
+        );
+        assert_eq!(inventory[1].kind, "thermometer");
+        assert_eq!(inventory[2].room, "Garage");
+        assert_eq!(inventory[2].kind, "device");
+    }
+
+    #[test]
+    fn room_sensors() {
+        let mut room = SmartRoom::new("Kitchen".to_string());
+        let mut thermometer = SmartThermometer::new("Sensor".to_string());
+        thermometer.set_temperature(Temperature::from_celsius(22.0));
+
+        let _ = room.plug(Arc::new(SmartSocket::new("Kettle".to_string())));
+        let _ = room.plug(Arc::new(thermometer));
+        let _ = room.plug(Arc::new(SmartSocket::new("Toaster".to_string())));
+
+        let sensors = room.sensors();
+
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].name(), "Sensor");
+        assert_eq!(sensors[0].read(), 22.0);
+    }
+
+    #[test]
+    fn house_with_rooms() {
+        let house = SmartHouse::with_rooms(
+            "Sweet home".to_string(),
+            vec![
+                SmartRoom::new("Kitchen".to_string()),
+                SmartRoom::new("Bedroom".to_string()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(house.name(), "Sweet home");
+        assert_eq!(house.room_names(), vec!["Kitchen", "Bedroom"]);
+
+        let result = SmartHouse::with_rooms(
+            "Sweet home".to_string(),
+            vec![
+                SmartRoom::new("Kitchen".to_string()),
+                SmartRoom::new("Kitchen".to_string()),
+            ],
+        );
+
+        assert!(matches!(
+            result,
+            Err(SmartHouseError::RoomAlreadyExists(name)) if name == "Kitchen"
+        ));
+    }
+
+    #[test]
+    fn report_kind_summary() {
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let _ = house.add(SmartRoom::new("Kitchen".to_string()));
+        let _ = house.add(SmartRoom::new("Bedroom".to_string()));
+
+        for (room, name) in [
+            ("Kitchen", "Kettle"),
+            ("Kitchen", "Toaster"),
+            ("Bedroom", "Lamp"),
+        ] {
+            let _ = house.plug(room, Arc::new(SmartSocket::new(name.to_string())));
+        }
+        let _ = house.plug("Kitchen", Arc::new(SmartThermometer::new("T1".to_string())));
+        let _ = house.plug("Bedroom", Arc::new(SmartThermometer::new("T2".to_string())));
+        let _ = house.plug("Bedroom", Arc::new(Robot::new("Vacuum".to_string())));
+
+        let printer: Arc<dyn Pluggable> = Arc::new(SmartSocket::new("Printer".to_string()));
+        let _ = house.plug_shared(&["Kitchen", "Bedroom"], printer);
+
+        let report = house.create_report(KindSummaryReport).unwrap();
+
+        assert_eq!(report, "device: 1\nsocket: 4\nthermometer: 2\n");
+    }
+
+    #[test]
+    fn socket_electrical_power() {
+        let socket = SmartSocket::electrical("Heater".to_string(), 230.0, 10.0);
+        assert_eq!(socket.power_usage(), 0.0);
+
+        socket.turn_on();
+        assert_eq!(socket.power_usage(), 2300.0);
+        assert_eq!(socket.volts(), Some(230.0));
+        assert_eq!(socket.amps(), Some(10.0));
+
+        let socket = SmartSocket::with_power("Lamp".to_string(), 60.0);
+        assert_eq!(socket.volts(), None);
+        assert_eq!(socket.amps(), None);
+    }
+
+    #[test]
+    fn normalized_room_treats_padded_names_as_duplicates() {
+        let mut room = SmartRoom::new_normalized(" Living  room ".to_string());
+        assert_eq!(room.name(), "Living room");
+
+        room.plug(Arc::new(SmartSocket::new("TV".to_string())))
+            .unwrap();
+        assert_eq!(
+            room.plug(Arc::new(SmartSocket::new(" TV ".to_string()))),
+            Err(SmartRoomError::DeviceAlreadyPlugged(" TV ".to_string()))
+        );
+
+        assert!(room.contains(" TV "));
+        assert!(room.contains("TV"));
+        assert!(room.is_connected(&SmartSocket::new("  TV".to_string())));
+
+        room.plug(Arc::new(SmartSocket::new("Lamp".to_string())))
+            .unwrap();
+        room.move_device_to(" TV ", 1).unwrap();
+        assert_eq!(room.devices(), vec!["Lamp", "TV"]);
+        room.unplug("Lamp");
+
+        room.unplug(" TV ");
+        assert!(room.devices().is_empty());
+        assert!(!room.contains("TV"));
+
+        let mut plain = SmartRoom::new("Hall".to_string());
+        plain
+            .plug(Arc::new(SmartSocket::new("TV".to_string())))
+            .unwrap();
+        assert!(plain
+            .plug(Arc::new(SmartSocket::new(" TV ".to_string())))
+            .is_ok());
+        assert!(!plain.contains("  TV"));
+    }
+
+    #[test]
+    fn redacted_report_hides_device_names() {
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(SmartRoom::new("Kitchen".to_string())).unwrap();
+        house.add(SmartRoom::new("Bedroom".to_string())).unwrap();
+        house
+            .plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string())))
+            .unwrap();
+        house
+            .plug(
+                "Bedroom",
+                Arc::new(SmartThermometer::new("Thermo".to_string())),
+            )
+            .unwrap();
+        house
+            .plug("Bedroom", Arc::new(SmartSocket::new("Lamp".to_string())))
+            .unwrap();
+
+        let report = house
+            .create_report(RedactedReport::new(TreeReport))
+            .unwrap();
+
+        for name in ["Kettle", "Thermo", "Lamp"] {
+            assert!(!report.contains(name), "{} leaked", name);
+        }
+        assert_eq!(
+            report,
+            "Home\n├── Kitchen\n│   └── device_1\n└── Bedroom\n    ├── device_2\n    └── device_3\n"
+        );
+    }
+
+    #[test]
+    fn redacted_report_keeps_shared_devices_shared() {
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(SmartRoom::new("Office".to_string())).unwrap();
+        house.add(SmartRoom::new("Hall".to_string())).unwrap();
+        let printer = SmartSocketBuilder::new("Printer".to_string())
+            .power(1000.0)
+            .initially_on(true)
+            .build();
+        house
+            .plug_shared(&["Office", "Hall"], Arc::new(printer))
+            .unwrap();
+
+        let energy = house
+            .create_report(RedactedReport::new(EnergyReport::default()))
+            .unwrap();
+        assert_eq!(
+            energy,
+            "-> House: Home\n--> Room: Office\ndevice_1: 1 kW\n--> Room: Hall\ndevice_1: 1 kW\nTotal: 1 kW\n"
+        );
+
+        let kinds = house
+            .create_report(RedactedReport::new(KindSummaryReport))
+            .unwrap();
+        assert_eq!(kinds, house.create_report(KindSummaryReport).unwrap());
+        assert_eq!(kinds, "socket: 1\n");
+    }
+
+    #[test]
+    fn count_empty_rooms() {
+        let mut house = SmartHouse::new("Home".to_string());
+        for name in ["Kitchen", "Bedroom", "Hall", "Garage"] {
+            house.add(SmartRoom::new(name.to_string())).unwrap();
+        }
+        house
+            .plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string())))
+            .unwrap();
+        house
+            .plug(
+                "Hall",
+                Arc::new(SmartThermometer::new("Thermo".to_string())),
+            )
+            .unwrap();
+
+        assert_eq!(house.count_rooms_where(|room| room.devices().is_empty()), 2);
+        assert_eq!(house.count_rooms_where(|_| true), 4);
+        assert_eq!(house.count_rooms_where(|room| room.name() == "Attic"), 0);
+    }
+
+    #[test]
+    fn subroom_socket_counts_towards_parent_power() {
+        let socket = SmartSocketBuilder::new("Light".to_string())
+            .power(40.0)
+            .initially_on(true)
+            .build();
+        let mut closet = SmartRoom::new("Closet".to_string());
+        closet.plug(Arc::new(socket)).unwrap();
+
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        bedroom
+            .plug(Arc::new(
+                SmartSocketBuilder::new("Lamp".to_string())
+                    .power(60.0)
+                    .initially_on(true)
+                    .build(),
+            ))
+            .unwrap();
+        assert_eq!(bedroom.power_usage(), 60.0);
+
+        bedroom.add_subroom(closet).unwrap();
+        assert_eq!(bedroom.power_usage(), 100.0);
+        assert_eq!(bedroom.all_devices(), vec!["Lamp", "Light"]);
+
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(bedroom).unwrap();
+        assert_eq!(house.try_total_power(), Ok(100.0));
+        assert_eq!(
+            house.create_report(TreeReport).unwrap(),
+            "Home\n└── Bedroom\n    ├── Lamp\n    └── Closet\n        └── Light\n"
+        );
+    }
+
+    #[test]
+    fn reports_include_subrooms() {
+        let light = SmartSocketBuilder::new("Light".to_string())
+            .power(1500.0)
+            .initially_on(true)
+            .build();
+        let mut closet = SmartRoom::new("Closet".to_string());
+        closet.plug(Arc::new(light)).unwrap();
+
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        bedroom
+            .plug(Arc::new(SmartSocket::new("Lamp".to_string())))
+            .unwrap();
+        bedroom.add_subroom(closet).unwrap();
+
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(bedroom).unwrap();
+
+        assert_eq!(
+            house.create_report(EnergyReport::default()).unwrap(),
+            "-> House: Home\n--> Room: Bedroom\nLamp: 0 W\n    --> Room: Closet\n    Light: 1.5 kW\nTotal: 1.5 kW\n"
+        );
+
+        let dot = house.create_report(DotReport).unwrap();
+        assert!(dot.contains("    room0 -> room1;\n"));
+        assert!(dot.contains("    room1 -> device1;\n"));
+
+        let json = make_report(ReportFormat::Json).make(&house).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"Home","rooms":[{"name":"Bedroom","devices":["Lamp"],"subrooms":[{"name":"Closet","devices":["Light"]}]}]}
+"#
+        );
+
+        let csv = make_report(ReportFormat::Csv).make(&house).unwrap();
+        assert_eq!(csv, "room,device\nBedroom,Lamp\nBedroom/Closet,Light\n");
+
+        let markdown = make_report(ReportFormat::Markdown).make(&house).unwrap();
+        assert_eq!(
+            markdown,
+            "# Home\n\n## Bedroom\n\n- Lamp\n\n### Closet\n\n- Light\n"
+        );
+
+        let html = house.create_report(HtmlReport).unwrap();
+        assert!(html.contains("<h3>Closet</h3>\n<ul>\n<li>Light</li>\n</ul>\n"));
+
+        let baseline = house.clone();
+        house
+            .get_room_mut("Bedroom")
+            .unwrap()
+            .add_subroom(SmartRoom::new("Balcony".to_string()))
+            .unwrap();
+        let diff = house.create_report(DiffReport { baseline }).unwrap();
+        assert_eq!(diff, "+ rooms\nBedroom/Balcony\n");
+    }
+
+    #[test]
+    fn validate_looks_into_subrooms() {
+        let mut closet = SmartRoom::new("Closet".to_string());
+        closet
+            .plug(Arc::new(SmartSocket::new("Light".to_string())))
+            .unwrap();
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        bedroom.add_subroom(closet).unwrap();
+        bedroom
+            .add_subroom(SmartRoom::new("Balcony".to_string()))
+            .unwrap();
+
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(bedroom).unwrap();
+
+        assert_eq!(
+            house.validate(),
+            vec![ValidationIssue::EmptyRoom("Balcony".to_string())]
+        );
+    }
+
+    #[test]
+    fn subroom_devices_count_in_every_aggregate() {
+        let light = SmartSocketBuilder::new("Light".to_string())
+            .power(1500.0)
+            .initially_on(true)
+            .build();
+        let mut closet = SmartRoom::new("Closet".to_string());
+        closet.plug(Arc::new(light)).unwrap();
+        closet
+            .plug(Arc::new(SmartThermometer::new("Sensor".to_string())))
+            .unwrap();
+
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+        bedroom
+            .plug(Arc::new(SmartSocket::new("Lamp".to_string())))
+            .unwrap();
+        bedroom.add_subroom(closet).unwrap();
+
+        assert_eq!(bedroom.active_count(), 1);
+        assert_eq!(bedroom.sensors().len(), 1);
+        assert_eq!(bedroom.over_power_devices(1000.0).len(), 1);
+
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(bedroom).unwrap();
+
+        assert_eq!(house.device_count(), 3);
+        assert_eq!(house.active_count(), 1);
+        assert_eq!(
+            house
+                .all_devices_with_room()
+                .map(|(room, device)| format!("{}/{}", room, device.name()))
+                .collect::<Vec<_>>(),
+            vec!["Bedroom/Lamp", "Closet/Light", "Closet/Sensor"]
+        );
+        assert_eq!(house.inventory().len(), 3);
+
+        let (room, device) = house.find_device("Light").unwrap();
+        assert_eq!(room.name(), "Closet");
+        assert_eq!(device.name(), "Light");
+        assert_eq!(house.find_all_devices("Sensor").len(), 1);
+
+        assert_eq!(
+            house.snapshot().rooms[0].subrooms[0].devices,
+            vec!["Light", "Sensor"]
+        );
+        assert!(house
+            .export_env()
+            .contains("ROOM_1_SUBROOM_1_DEVICE_1=Light\n"));
+
+        house.all_off();
+        assert_eq!(house.active_count(), 0);
+    }
+
+    #[test]
+    fn prefix_room_names() {
+        let mut house = SmartHouse::new("Home".to_string());
+        for name in ["Kitchen", "Bedroom", "Hall"] {
+            house.add(SmartRoom::new(name.to_string())).unwrap();
+        }
+        house
+            .plug("Kitchen", Arc::new(SmartSocket::new("Kettle".to_string())))
+            .unwrap();
+        let before = house.version();
+
+        house.prefix_room_names("tenant1:").unwrap();
+
+        assert_eq!(
+            house.room_names(),
+            vec!["tenant1:Kitchen", "tenant1:Bedroom", "tenant1:Hall"]
+        );
+        assert_eq!(house.room_index("Kitchen"), None);
+        assert_eq!(house.get_rooms()[0].devices(), vec!["Kettle"]);
+        assert!(house.version() > before);
+    }
+
+    #[test]
+    fn socket_round_trips_through_device_record() {
+        let mut socket = SmartSocket::electrical("Kettle".to_string(), 230.0, 8.5);
+        socket.upgrade_firmware("1.2.3".to_string());
+        socket.set_metadata("vendor".to_string(), "Acme".to_string());
+        socket.set_power_limit(1000.0);
+        socket.turn_on();
+
+        let record = socket.serialize_kind();
+        assert_eq!(record.kind, "socket");
+        assert_eq!(record.name, "Kettle");
+
+        let restored = record.clone().into_device();
+        assert_eq!(restored.name(), "Kettle");
+        assert_eq!(restored.kind(), "socket");
+        assert!(restored.is_on());
+        assert_eq!(restored.power_usage(), socket.power_usage());
+        assert_eq!(restored.firmware(), "1.2.3");
+        assert_eq!(restored.metadata().get("vendor").unwrap(), "Acme");
+        assert_eq!(restored.health(), socket.health());
+        assert!(matches!(restored.health(), DeviceHealth::Warning(_)));
+        assert_eq!(restored.serialize_kind(), record);
+
+        let unknown = DeviceRecord::new("robot", "R2").into_device();
+        assert_eq!(unknown.name(), "R2");
+        assert_eq!(unknown.kind(), "device");
+    }
+
+    #[test]
+    fn find_device_follows_moved_device() {
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(SmartRoom::new("Kitchen".to_string())).unwrap();
+        house.add(SmartRoom::new("Bedroom".to_string())).unwrap();
+        house
+            .plug("Kitchen", Arc::new(SmartSocket::new("Lamp".to_string())))
+            .unwrap();
+
+        let (room, lamp) = house.find_device("Lamp").unwrap();
+        assert_eq!(room.name(), "Kitchen");
+
+        house.get_room_mut("Kitchen").unwrap().unplug("Lamp");
+        house.get_room_mut("Bedroom").unwrap().plug(lamp).unwrap();
+
+        assert_eq!(house.find_device("Lamp").unwrap().0.name(), "Bedroom");
+        assert_eq!(house.find_all_devices("Lamp").len(), 1);
+
+        for room in house.iter_rooms_mut() {
+            room.unplug("Lamp");
+        }
+        assert!(house.find_device("Lamp").is_none());
+    }
+
+    #[test]
+    fn find_device_tracks_subrooms_and_room_order() {
+        let mut house = SmartHouse::new("Home".to_string());
+        house.add(SmartRoom::new("Kitchen".to_string())).unwrap();
+        house.add(SmartRoom::new("Bedroom".to_string())).unwrap();
+        house.add(SmartRoom::new("Hall".to_string())).unwrap();
+
+        let mut closet = SmartRoom::new("Closet".to_string());
+        closet
+            .plug(Arc::new(SmartSocket::new("Light".to_string())))
+            .unwrap();
+        house
+            .get_room_mut("Bedroom")
+            .unwrap()
+            .add_subroom(closet)
+            .unwrap();
+        assert_eq!(house.find_device("Light").unwrap().0.name(), "Closet");
+
+        house
+            .plug("Hall", Arc::new(SmartSocket::new("Light".to_string())))
+            .unwrap();
+        house.swap_rooms("Bedroom", "Hall").unwrap();
+        assert_eq!(house.find_device("Light").unwrap().0.name(), "Hall");
+
+        house.del("Kitchen").unwrap();
+        house.del("Hall").unwrap();
+        assert_eq!(house.find_device("Light").unwrap().0.name(), "Closet");
+        assert_eq!(house.find_all_devices("Light").len(), 1);
+
+        let copy = house.clone();
+        assert_eq!(copy.find_device("Light").unwrap().0.name(), "Closet");
+    }
+
+    #[test]
+    fn format_power_picks_unit() {
+        assert_eq!(format_power(950.0), "950 W");
+        assert_eq!(format_power(1500.0), "1.5 kW");
+        assert_eq!(format_power(1234.0), "1.2 kW");
+        assert_eq!(format_power(3_250_000.0), "3.3 MW");
+        assert_eq!(format_power(0.0), "0 W");
+
+        assert_eq!(format_power(999.94), "999.9 W");
+        assert_eq!(format_power(999.96), "1 kW");
+        assert_eq!(format_power(1_000.0), "1 kW");
+        assert_eq!(format_power(999_940.0), "999.9 kW");
+        assert_eq!(format_power(999_999.0), "1 MW");
+        assert_eq!(format_power(1_000_000.0), "1 MW");
+        assert_eq!(format_power(-1500.0), "-1.5 kW");
+        assert_eq!(format_power(-0.01), "0 W");
+        assert_eq!(PowerUnit::Watts.format(-0.01), "0 W");
+        assert_eq!(PowerUnit::Watts.format(0.333), "0.3 W");
+        assert_eq!(PowerUnit::Kilowatts.format(1000.0 / 3.0), "0.3 kW");
+
+        let mut house = SmartHouse::new("Sweet home".to_string());
+        let mut kitchen = SmartRoom::new("Kitchen".to_string());
+        let kettle = SmartSocket::with_power("Kettle".to_string(), 1500.0);
+        let lamp = SmartSocket::with_power("Lamp".to_string(), 60.0);
+        kettle.turn_on();
+        lamp.turn_on();
+        kitchen.plug(Arc::new(kettle)).unwrap();
+        kitchen.plug(Arc::new(lamp)).unwrap();
+        house.add(kitchen).unwrap();
+
+        assert_eq!(
+            house.create_report(EnergyReport::default()).unwrap(),
+            "-> House: Sweet home\n--> Room: Kitchen\nKettle: 1.5 kW\nLamp: 60 W\nTotal: 1.6 kW\n"
+        );
+    }
+
+    #[test]
+    fn deep_clone_shares_devices_without_clone_box() {
+        let mut room = SmartRoom::new("Lab".to_string());
+        room.plug(Arc::new(Robot::new("R2".to_string()))).unwrap();
+        room.plug(Arc::new(SmartSocket::new("Lamp".to_string())))
+            .unwrap();
+
+        let deep = room.deep_clone();
+
+        assert!(Robot::new("R2".to_string()).clone_box().is_none());
+        assert!(Arc::ptr_eq(&deep.devices_arc()[0], &room.devices_arc()[0]));
+        assert!(!Arc::ptr_eq(&deep.devices_arc()[1], &room.devices_arc()[1]));
+    }
+
+    #[test]
+    fn socket_display_matches_trait_object() {
+        let socket = SmartSocket::with_power("Printer".to_string(), 1500.0);
+        socket.turn_on();
+
+        let expected = "socket Printer (on, 1500W)";
+        assert_eq!(format!("{}", socket), expected);
+
+        let device: Arc<dyn Pluggable> = Arc::new(socket);
+        assert_eq!(format!("{}", device), expected);
+    }
+
+    #[test]
+    fn thermometer_display_matches_trait_object() {
+        let thermometer = SmartThermometer::new("Sensor".to_string());
+
+        let expected = "thermometer Sensor (0°C)";
+        assert_eq!(format!("{}", thermometer), expected);
+
+        let device: Arc<dyn Pluggable> = Arc::new(thermometer);
+        assert_eq!(format!("{}", device), expected);
+    }
+
+    #[test]
+    fn add_subroom_validates_names() {
+        let mut bedroom = SmartRoom::new("Bedroom".to_string());
+
+        assert_eq!(
+            bedroom.add_subroom(SmartRoom::new("Closet".to_string())),
+            Ok(())
+        );
+        assert_eq!(
+            bedroom.add_subroom(SmartRoom::new("Closet".to_string())),
+            Err(SmartRoomError::SubroomAlreadyExists("Closet".to_string()))
+        );
+        assert_eq!(
+            bedroom.add_subroom(SmartRoom::new(" ".to_string())),
+            Err(SmartRoomError::InvalidSubroomName(" ".to_string()))
+        );
+        assert_eq!(bedroom.subrooms().len(), 1);
+    }
+
+    pub mod custom {
+        use std::cell::Cell;
+        use std::error::Error;
+
+        use lesson_4::smart::device::DeviceHealth;
+        use lesson_4::smart::location::SmartHouse;
+        use lesson_4::smart::{Named, Pluggable, Reportable};
+
+        pub struct Robot {
+            name: String,
+        }
+
+        impl Robot {
+            pub fn new(name: String) -> Self {
+                Self { name }
+            }
+        }
+
+        impl Named for Robot {
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+
+        impl Pluggable for Robot {}
+
+        pub struct Heater {
+            name: String,
+            power: f64,
+        }
+
+        impl Heater {
+            pub fn new(name: String, power: f64) -> Self {
+                Self { name, power }
+            }
+        }
+
+        impl Named for Heater {
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+
+        pub struct Broken {
+            name: String,
+        }
+
+        impl Broken {
+            pub fn new(name: String) -> Self {
+                Self { name }
+            }
+        }
+
+        impl Named for Broken {
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+
+        impl Pluggable for Broken {
+            fn health(&self) -> DeviceHealth {
+                DeviceHealth::Fault("heating element broken".to_string())
+            }
+        }
+
+        #[derive(Default)]
+        pub struct CountingReport {
+            calls: Cell<usize>,
+        }
+
+        impl CountingReport {
+            pub fn calls(&self) -> usize {
+                self.calls.get()
+            }
+        }
+
+        impl Reportable for CountingReport {
+            fn make(&self, house: &SmartHouse) -> Result<String, Box<dyn Error>> {
+                self.calls.set(self.calls.get() + 1);
+
+                Ok(format!("{} rooms", house.get_rooms().len()))
+            }
+        }
+
+        impl Pluggable for Heater {
+            fn power_usage(&self) -> f64 {
+                self.power
+            }
+        }
     }
 }